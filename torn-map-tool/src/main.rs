@@ -1,8 +1,11 @@
-use std::{collections::HashMap, io::Write};
+use std::{io::Write, time::Duration};
 
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use image::{codecs::png::PngEncoder, DynamicImage, GenericImageView, ImageEncoder, ImageFormat};
-use torn_territories::{fit_view_box, RenderScale, TerritoryId, TerritoryIdError};
+use torn_territories::{
+    AnimationFrame, AnimationOptions, EdgePolicy, Framing, OwnershipTimeline, Padding, RenderScale,
+    TerritoryDataset, TerritoryIdError, TerritoryMap,
+};
 
 #[derive(Parser)]
 #[command(author, about, version)]
@@ -14,6 +17,11 @@ struct Cli {
     #[arg(short, long, value_enum, default_value_t = OutputFormat::Png)]
     format: OutputFormat,
 
+    /// territory_shapes.json file to use instead of the embedded dataset. It can change or leave
+    /// out territories, but not add ids that are unknown to this version of the tool
+    #[arg(short, long)]
+    dataset: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -22,6 +30,8 @@ struct Cli {
 enum Commands {
     MapSegment(MapSegmentArgs),
     TerritoryView(TerritoryViewArgs),
+    DiffData(DiffDataArgs),
+    Animate(AnimateArgs),
 }
 
 #[derive(Args)]
@@ -77,17 +87,96 @@ struct TerritoryViewArgs {
     aspect_ratio: f32,
 
     #[arg(long, num_args(0..), value_parser = parse_rendering_instructions)]
-    fill: Vec<HashMap<TerritoryId, torn_territories::RenderInstruction>>,
+    fill: Vec<TerritoryMap<torn_territories::RenderInstruction>>,
 
     #[arg(long, num_args(0..), value_parser = parse_rendering_instructions)]
-    border: Vec<HashMap<TerritoryId, torn_territories::RenderInstruction>>,
+    border: Vec<TerritoryMap<torn_territories::RenderInstruction>>,
+
+    /// frame all territories of the sector instead of the listed ones
+    #[arg(long, conflicts_with = "territories")]
+    sector: Option<u8>,
+
+    /// territories that should be framed together
+    #[arg(
+        required_unless_present_any = ["sector", "whole_map"],
+        value_parser = torn_territories::TerritoryId::parse_lenient
+    )]
+    territories: Vec<torn_territories::TerritoryId>,
+}
+
+#[derive(Args)]
+struct DiffDataArgs {
+    /// previous version of territory_shapes.json
+    old: String,
 
-    territory: torn_territories::TerritoryId,
+    /// new version of territory_shapes.json
+    new: String,
+
+    /// distance in map pixels that a shape has to move before it is reported as changed
+    #[arg(short, long, default_value_t = 1f64)]
+    threshold: f64,
+
+    /// render the changed territories onto the map and write the image to the output file
+    #[arg(short, long, default_value_t = false)]
+    render: bool,
+}
+
+#[derive(Args)]
+struct AnimateArgs {
+    /// JSON file with a list of frames, or an ownership timeline if --step is set
+    input: String,
+
+    /// take a frame every STEP seconds from the timeline in the input file
+    #[arg(long)]
+    step: Option<i64>,
+
+    /// time of the first frame, defaults to the first change in the timeline
+    #[arg(long, requires = "step")]
+    from: Option<i64>,
+
+    /// time of the last frame, defaults to the last change in the timeline
+    #[arg(long, requires = "step")]
+    to: Option<i64>,
+
+    /// territories to frame instead of showing the whole map
+    #[arg(short, long = "territory", num_args(1..), value_parser = torn_territories::TerritoryId::parse_lenient)]
+    territories: Vec<torn_territories::TerritoryId>,
+
+    /// frame all territories of the sector instead of showing the whole map
+    #[arg(long, conflicts_with = "territories")]
+    sector: Option<u8>,
+
+    #[arg(short, long, default_value_t = 1f32)]
+    factor: f32,
+
+    #[arg(short, long, default_value_t = 4f32/3f32)]
+    aspect_ratio: f32,
+
+    /// how long each frame is shown in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    delay: u64,
+
+    #[arg(long, value_enum, default_value_t = AnimationFormat::Gif)]
+    animation_format: AnimationFormat,
+
+    /// draw a legend of the owners onto every frame
+    #[arg(long, default_value_t = false)]
+    legend: bool,
+
+    /// leave out the captions of the frames
+    #[arg(long, default_value_t = false)]
+    no_captions: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
+enum AnimationFormat {
+    Gif,
+    Apng,
 }
 
 fn parse_rendering_instructions(
     s: &str,
-) -> Result<HashMap<TerritoryId, torn_territories::RenderInstruction>, String> {
+) -> Result<TerritoryMap<torn_territories::RenderInstruction>, String> {
     let (colour, rest) = s
         .split_once(':')
         .ok_or("invalid rendering instruction. Expected <colour>:<opacity>:<territory ids>")?;
@@ -108,10 +197,10 @@ fn parse_rendering_instructions(
 
     let inst = torn_territories::RenderInstruction { colour, opacity };
 
-    let mut res = HashMap::new();
+    let mut res = TerritoryMap::new();
     for id in terts.split(',') {
         res.insert(
-            id.parse()
+            torn_territories::TerritoryId::parse_lenient(id)
                 .map_err(|why: TerritoryIdError| why.to_string())?,
             inst.clone(),
         );
@@ -141,29 +230,45 @@ fn load_map_segment(args: MapSegmentArgs) -> DynamicImage {
     DynamicImage::ImageLuma8(image)
 }
 
-fn load_territory_view(args: TerritoryViewArgs) -> DynamicImage {
-    let path = torn_territories::path_for_territory(args.territory)
-        .unwrap_or_else(|| panic!("Territory with id '{}' does not exist!", args.territory));
+fn load_territory_view(dataset: &TerritoryDataset, args: TerritoryViewArgs) -> DynamicImage {
     let bbox = if args.whole_map {
         torn_territories::MAP_BBOX
     } else {
-        fit_view_box(torn_territories::bbox_for_path(
-            &path,
+        // clap requires territories unless a sector or the whole map are selected
+        view_port(
+            dataset,
+            &args.territories,
+            args.sector,
             args.factor,
             args.aspect_ratio,
-        ))
+        )
+        .unwrap()
     };
 
-    let fill = args.fill.into_iter().fold(HashMap::new(), |mut acc, f| {
-        acc.extend(f);
-        acc
-    });
-    let stroke = args.border.into_iter().fold(HashMap::new(), |mut acc, f| {
-        acc.extend(f);
-        acc
-    });
+    let fill = args
+        .fill
+        .into_iter()
+        .fold(TerritoryMap::new(), |mut acc, f| {
+            acc.extend(f);
+            acc
+        });
+    let stroke = args
+        .border
+        .into_iter()
+        .fold(TerritoryMap::new(), |mut acc, f| {
+            acc.extend(f);
+            acc
+        });
+    if let Some(id) = fill
+        .keys()
+        .iter()
+        .chain(stroke.keys().iter())
+        .find(|id| !dataset.contains(*id))
+    {
+        invalid_value(format!("Territory '{id}' is not part of the dataset"));
+    }
 
-    let shapes = torn_territories::render_territories(
+    let shapes = dataset.render_territories(
         bbox,
         fill,
         stroke,
@@ -177,12 +282,185 @@ fn load_territory_view(args: TerritoryViewArgs) -> DynamicImage {
     DynamicImage::ImageRgba8(shapes)
 }
 
+/// Upper bound for the frames taken from a timeline, every frame is rendered into memory.
+const MAX_FRAMES: i64 = 10_000;
+
+fn invalid_value(message: String) -> ! {
+    Cli::command()
+        .error(clap::error::ErrorKind::InvalidValue, message)
+        .exit()
+}
+
+/// The view port around the territories, or around all territories of the sector if it is set.
+/// `None` if there is nothing to frame.
+fn view_port(
+    dataset: &TerritoryDataset,
+    territories: &[torn_territories::TerritoryId],
+    sector: Option<u8>,
+    factor: f32,
+    aspect_ratio: f32,
+) -> Option<image::math::Rect> {
+    let ids: Vec<_> = match sector {
+        Some(sector) => dataset
+            .iter()
+            .filter(|(_, info)| info.sector == sector)
+            .map(|(id, _)| id)
+            .collect(),
+        None => territories.to_vec(),
+    };
+    if let Some(sector) = sector.filter(|_| ids.is_empty()) {
+        invalid_value(format!("Sector {sector} does not have any territories"));
+    }
+    if let Some(id) = ids.iter().find(|id| !dataset.contains(**id)) {
+        invalid_value(format!("Territory '{id}' is not part of the dataset"));
+    }
+    let is_positive = |value: f32| value.is_finite() && value > 0f32;
+    if !(is_positive(factor) && is_positive(aspect_ratio)) {
+        invalid_value("--factor and --aspect-ratio need to be positive and finite".to_owned());
+    }
+
+    // the territories take up `factor` of the frame along its tighter axis
+    let framing = Framing {
+        padding: Padding::Percent((1f32 / factor - 1f32) * 50f32),
+        aspect_ratio: Some(aspect_ratio),
+        min_size: None,
+        max_size: None,
+        edge_policy: EdgePolicy::Shift,
+    };
+    let frame = dataset.frame_territories(&ids, &framing)?;
+    Some(frame.view_port)
+}
+
+fn diff_data(args: DiffDataArgs) -> Result<Option<DynamicImage>, String> {
+    let old = read_file(&args.old)?;
+    let new = read_file(&args.new)?;
+
+    let diff = torn_territories::diff_json_datasets(&old, &new, args.threshold)
+        .map_err(|why| format!("Failed to compare the datasets: {why}"))?;
+    print!("{diff}");
+
+    if !args.render {
+        return Ok(None);
+    }
+
+    let image = diff.render(torn_territories::MAP_BBOX, RenderScale::X4);
+    Ok(Some(DynamicImage::ImageRgba8(image)))
+}
+
+fn animate(dataset: &TerritoryDataset, args: AnimateArgs) -> Vec<u8> {
+    let json = std::fs::read(&args.input)
+        .unwrap_or_else(|why| panic!("Failed to read '{}': {why}", args.input));
+
+    let mut frames: Vec<AnimationFrame> = match args.step {
+        Some(step) => {
+            if step <= 0 {
+                invalid_value("--step needs to be positive".to_owned());
+            }
+
+            let timeline: OwnershipTimeline =
+                serde_json::from_slice(&json).unwrap_or_else(|why| panic!("{why}"));
+            let (first, last) = timeline.span().unwrap_or_default();
+            let from = args.from.unwrap_or(first);
+            let to = args.to.unwrap_or(last);
+            if to < from {
+                invalid_value(format!("--to {to} is before --from {from}"));
+            }
+            let count = to
+                .checked_sub(from)
+                .and_then(|span| (span / step).checked_add(1))
+                .filter(|count| *count <= MAX_FRAMES)
+                .unwrap_or_else(|| {
+                    invalid_value(format!(
+                        "too many frames from {from} to {to}, at most {MAX_FRAMES} are supported. Increase --step"
+                    ))
+                });
+
+            // `from + i * step` stays within `from..=to`, so it cannot overflow
+            let times = (0..count).map(|i| from + i * step);
+            AnimationFrame::from_timeline(&timeline, times)
+        }
+        None => serde_json::from_slice(&json).unwrap_or_else(|why| panic!("{why}")),
+    };
+    if args.no_captions {
+        frames.iter_mut().for_each(|frame| frame.caption = None);
+    }
+
+    let framed = view_port(
+        dataset,
+        &args.territories,
+        args.sector,
+        args.factor,
+        args.aspect_ratio,
+    );
+    let (view_port, scale) = match framed {
+        Some(view_port) => (view_port, RenderScale::X1),
+        None => (torn_territories::MAP_BBOX, RenderScale::X4),
+    };
+
+    let options = AnimationOptions {
+        format: match args.animation_format {
+            AnimationFormat::Gif => torn_territories::AnimationFormat::Gif,
+            AnimationFormat::Apng => torn_territories::AnimationFormat::Apng,
+        },
+        delay: Duration::from_millis(args.delay),
+        legend: args.legend,
+    };
+
+    dataset
+        .render_animation(&frames, view_port, scale, &options)
+        .unwrap_or_else(|why| panic!("{why}"))
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|why| format!("Failed to read '{path}': {why}"))
+}
+
+fn load_dataset(path: &str) -> Result<TerritoryDataset, String> {
+    TerritoryDataset::from_json(&read_file(path)?)
+        .map_err(|why| format!("Failed to load dataset '{path}': {why}"))
+}
+
 fn main() {
     let cli = Cli::parse();
 
+    let dataset = match &cli.dataset {
+        Some(path) => load_dataset(path).unwrap_or_else(|why| {
+            Cli::command()
+                .error(clap::error::ErrorKind::InvalidValue, why)
+                .exit()
+        }),
+        None => TerritoryDataset::default(),
+    };
+
     let image = match cli.command {
+        Commands::Animate(args) => {
+            let animation = animate(&dataset, args);
+            match cli.output_file {
+                Some(out_file) => std::fs::write(out_file, animation).unwrap(),
+                None => std::io::stdout().write_all(&animation).unwrap(),
+            }
+            return;
+        }
         Commands::MapSegment(args) => load_map_segment(args),
-        Commands::TerritoryView(args) => load_territory_view(args),
+        Commands::TerritoryView(args) => load_territory_view(&dataset, args),
+        Commands::DiffData(args) => {
+            if args.render && cli.output_file.is_none() {
+                Cli::command()
+                    .error(
+                        clap::error::ErrorKind::MissingRequiredArgument,
+                        "--render requires --output-file to be set",
+                    )
+                    .exit();
+            }
+
+            match diff_data(args) {
+                Ok(Some(image)) => image,
+                Ok(None) => return,
+                Err(why) => Cli::command()
+                    .error(clap::error::ErrorKind::InvalidValue, why)
+                    .exit(),
+            }
+        }
     };
 
     if let Some(out_file) = cli.output_file {
@@ -0,0 +1,24 @@
+use std::{env, fs, path::Path};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-env-changed=TT_UPDATE_HEADER");
+
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let header = Path::new(&out_dir).join("torn_territories.h");
+    cbindgen::generate(&crate_dir)
+        .expect("failed to generate the C header")
+        .write_to_file(&header);
+
+    // the committed header is only updated on request, see the crate documentation
+    if env::var_os("TT_UPDATE_HEADER").is_some() {
+        fs::copy(
+            &header,
+            Path::new(&crate_dir).join("include/torn_territories.h"),
+        )
+        .expect("failed to update include/torn_territories.h");
+    }
+}
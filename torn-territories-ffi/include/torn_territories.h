@@ -0,0 +1,172 @@
+#ifndef TORN_TERRITORIES_H
+#define TORN_TERRITORIES_H
+
+/* Generated by cbindgen from src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ Result of every API call.
+ */
+typedef enum TtStatus {
+  TT_STATUS_OK = 0,
+  /*
+   A required pointer argument was NULL.
+   */
+  TT_STATUS_NULL_POINTER = 1,
+  /*
+   A string argument is not valid UTF-8.
+   */
+  TT_STATUS_INVALID_UTF8 = 2,
+  /*
+   The string is not the ID of a territory.
+   */
+  TT_STATUS_INVALID_ID = 3,
+  /*
+   The index does not belong to a territory of the embedded dataset.
+   */
+  TT_STATUS_UNKNOWN_TERRITORY = 4,
+  /*
+   The viewport is empty, outside of the map or too small for the scale.
+   */
+  TT_STATUS_INVALID_VIEWPORT = 5,
+  /*
+   The scale is neither 1 nor 4.
+   */
+  TT_STATUS_INVALID_SCALE = 6,
+  /*
+   An opacity is not between 0.0 and 1.0.
+   */
+  TT_STATUS_INVALID_OPACITY = 7,
+  /*
+   The output buffer cannot hold the result, see the function for the required size.
+   */
+  TT_STATUS_BUFFER_TOO_SMALL = 8,
+  /*
+   An unexpected internal error, this is a bug.
+   */
+  TT_STATUS_INTERNAL = 9,
+} TtStatus;
+
+/*
+ Metadata of a territory.
+ */
+typedef struct TtTerritoryInfo {
+  uint8_t sector;
+  uint16_t slots;
+  int32_t db_id;
+  size_t neighbor_count;
+} TtTerritoryInfo;
+
+/*
+ A rectangle in map pixels.
+ */
+typedef struct TtViewport {
+  uint32_t x;
+  uint32_t y;
+  uint32_t width;
+  uint32_t height;
+} TtViewport;
+
+/*
+ Fill or stroke of a single territory.
+ */
+typedef struct TtInstruction {
+  uint16_t territory;
+  uint8_t red;
+  uint8_t green;
+  uint8_t blue;
+  float opacity;
+} TtInstruction;
+
+/*
+ Returns a static, NUL-terminated description of `status`.
+ */
+const char *tt_status_message(enum TtStatus status);
+
+/*
+ Parses a territory ID, ignoring case and surrounding whitespace, and writes its index to `out`.
+
+ # Safety
+
+ `id` has to be a NUL-terminated string and `out` has to be valid for writes.
+ */
+enum TtStatus tt_parse_id(const char *id, uint16_t *out);
+
+/*
+ Writes the three letter ID of a territory and a terminating NUL to `out`.
+
+ # Safety
+
+ `out` has to be valid for writes of 4 bytes.
+ */
+enum TtStatus tt_id_to_str(uint16_t index, char *out);
+
+/*
+ Number of territories in the embedded dataset.
+ */
+size_t tt_territory_count(void);
+
+/*
+ Writes the indices of all territories, sorted by ID, to `out` and their number to `count`.
+ Returns [`TtStatus::BufferTooSmall`] if `len` is less than [`tt_territory_count`].
+
+ # Safety
+
+ `out` has to be valid for writes of `len` items and `count` has to be valid for writes.
+ */
+enum TtStatus tt_territory_ids(uint16_t *out, size_t len, size_t *count);
+
+/*
+ Writes the metadata of a territory to `out`.
+
+ # Safety
+
+ `out` has to be valid for writes.
+ */
+enum TtStatus tt_territory_info(uint16_t index, struct TtTerritoryInfo *out);
+
+/*
+ Writes the indices of the neighbors of a territory to `out` and their number to `count`.
+ Returns [`TtStatus::BufferTooSmall`] if `len` is less than
+ [`TtTerritoryInfo::neighbor_count`].
+
+ # Safety
+
+ `out` has to be valid for writes of `len` items and `count` has to be valid for writes.
+ */
+enum TtStatus tt_territory_neighbors(uint16_t index, uint16_t *out, size_t len, size_t *count);
+
+/*
+ Writes the size in bytes of the RGBA image that [`tt_render`] produces to `size`.
+
+ # Safety
+
+ `size` has to be valid for writes.
+ */
+enum TtStatus tt_render_buffer_size(struct TtViewport viewport, uint32_t scale, size_t *size);
+
+/*
+ Renders the viewport with the map background into `buf` as 8 bit RGBA rows without padding.
+ The image is `width / scale` by `height / scale` pixels, where `scale` is 1 or 4. Later
+ instructions for the same territory take precedence.
+
+ # Safety
+
+ `fill` and `stroke` have to be valid for reads of `fill_len` and `stroke_len` items, they may
+ be NULL if their length is 0. `buf` has to be valid for writes of `buf_len` bytes.
+ */
+enum TtStatus tt_render(struct TtViewport viewport,
+                        uint32_t scale,
+                        const struct TtInstruction *fill,
+                        size_t fill_len,
+                        const struct TtInstruction *stroke,
+                        size_t stroke_len,
+                        uint8_t *buf,
+                        size_t buf_len);
+
+#endif  /* TORN_TERRITORIES_H */
@@ -0,0 +1,511 @@
+//! C API for `torn-territories`. The header is generated into `OUT_DIR` by the build script, a
+//! copy is committed as `include/torn_territories.h`. After changing the API, update the copy with
+//! `TT_UPDATE_HEADER=1 cargo build -p torn-territories-ffi`.
+//!
+//! Territories are identified by their stable dense index, see `TerritoryId::index`. Functions
+//! report errors by returning a [`TtStatus`] and never unwind into the caller.
+
+use std::{
+    ffi::{c_char, CStr},
+    panic::{catch_unwind, AssertUnwindSafe},
+    slice,
+};
+
+use torn_territories::{
+    OutputFormat, RenderError, RenderInstruction, RenderLayer, RenderScale, RenderSpec,
+    TerritoryDataset, TerritoryId, Viewport,
+};
+
+/// Result of every API call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TtStatus {
+    Ok = 0,
+    /// A required pointer argument was NULL.
+    NullPointer = 1,
+    /// A string argument is not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The string is not the ID of a territory.
+    InvalidId = 3,
+    /// The index does not belong to a territory of the embedded dataset.
+    UnknownTerritory = 4,
+    /// The viewport is empty, outside of the map or too small for the scale.
+    InvalidViewport = 5,
+    /// The scale is neither 1 nor 4.
+    InvalidScale = 6,
+    /// An opacity is not between 0.0 and 1.0.
+    InvalidOpacity = 7,
+    /// The output buffer cannot hold the result, see the function for the required size.
+    BufferTooSmall = 8,
+    /// An unexpected internal error, this is a bug.
+    Internal = 9,
+}
+
+/// Metadata of a territory.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtTerritoryInfo {
+    pub sector: u8,
+    pub slots: u16,
+    pub db_id: i32,
+    pub neighbor_count: usize,
+}
+
+/// A rectangle in map pixels.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtViewport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Fill or stroke of a single territory.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TtInstruction {
+    pub territory: u16,
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub opacity: f32,
+}
+
+/// Runs `f`, turning panics into [`TtStatus::Internal`].
+fn guard(f: impl FnOnce() -> Result<(), TtStatus>) -> TtStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => TtStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => TtStatus::Internal,
+    }
+}
+
+fn territory(index: u16) -> Result<TerritoryId, TtStatus> {
+    TerritoryId::from_index(index).ok_or(TtStatus::UnknownTerritory)
+}
+
+/// Borrows a slice from a pointer and length, allowing NULL for empty slices.
+unsafe fn slice_from<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], TtStatus> {
+    if len == 0 {
+        Ok(&[])
+    } else if ptr.is_null() {
+        Err(TtStatus::NullPointer)
+    } else {
+        Ok(slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// Writes `items` to `out`, which can hold `len` items, and stores the number of items in `count`.
+/// With a NULL `out` and a `len` of 0 only `count` is written.
+unsafe fn write_items(
+    items: &[u16],
+    out: *mut u16,
+    len: usize,
+    count: *mut usize,
+) -> Result<(), TtStatus> {
+    if count.is_null() {
+        return Err(TtStatus::NullPointer);
+    }
+    *count = items.len();
+
+    if len < items.len() {
+        return Err(TtStatus::BufferTooSmall);
+    }
+    if !items.is_empty() {
+        if out.is_null() {
+            return Err(TtStatus::NullPointer);
+        }
+        slice::from_raw_parts_mut(out, items.len()).copy_from_slice(items);
+    }
+
+    Ok(())
+}
+
+/// Returns a static, NUL-terminated description of `status`.
+#[no_mangle]
+pub extern "C" fn tt_status_message(status: TtStatus) -> *const c_char {
+    let message: &'static CStr = match status {
+        TtStatus::Ok => c"ok",
+        TtStatus::NullPointer => c"a required pointer is NULL",
+        TtStatus::InvalidUtf8 => c"string is not valid UTF-8",
+        TtStatus::InvalidId => c"string is not a territory ID",
+        TtStatus::UnknownTerritory => c"index does not belong to a territory",
+        TtStatus::InvalidViewport => c"viewport is empty or outside of the map",
+        TtStatus::InvalidScale => c"scale has to be 1 or 4",
+        TtStatus::InvalidOpacity => c"opacity is not between 0.0 and 1.0",
+        TtStatus::BufferTooSmall => c"output buffer is too small",
+        TtStatus::Internal => c"internal error",
+    };
+    message.as_ptr()
+}
+
+/// Parses a territory ID, ignoring case and surrounding whitespace, and writes its index to `out`.
+///
+/// # Safety
+///
+/// `id` has to be a NUL-terminated string and `out` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_parse_id(id: *const c_char, out: *mut u16) -> TtStatus {
+    guard(|| {
+        if id.is_null() || out.is_null() {
+            return Err(TtStatus::NullPointer);
+        }
+        let id = CStr::from_ptr(id)
+            .to_str()
+            .map_err(|_| TtStatus::InvalidUtf8)?;
+        let id = TerritoryId::parse_lenient(id).map_err(|_| TtStatus::InvalidId)?;
+
+        *out = id.index();
+        Ok(())
+    })
+}
+
+/// Writes the three letter ID of a territory and a terminating NUL to `out`.
+///
+/// # Safety
+///
+/// `out` has to be valid for writes of 4 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_id_to_str(index: u16, out: *mut c_char) -> TtStatus {
+    guard(|| {
+        if out.is_null() {
+            return Err(TtStatus::NullPointer);
+        }
+        let id = territory(index)?;
+
+        let out = slice::from_raw_parts_mut(out as *mut u8, 4);
+        out[..3].copy_from_slice(id.as_str().as_bytes());
+        out[3] = 0;
+        Ok(())
+    })
+}
+
+/// Number of territories in the embedded dataset.
+#[no_mangle]
+pub extern "C" fn tt_territory_count() -> usize {
+    TerritoryDataset::embedded().len()
+}
+
+/// Writes the indices of all territories, sorted by ID, to `out` and their number to `count`.
+/// Returns [`TtStatus::BufferTooSmall`] if `len` is less than [`tt_territory_count`].
+///
+/// # Safety
+///
+/// `out` has to be valid for writes of `len` items and `count` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_territory_ids(
+    out: *mut u16,
+    len: usize,
+    count: *mut usize,
+) -> TtStatus {
+    guard(|| {
+        let mut ids: Vec<_> = TerritoryDataset::embedded().ids().collect();
+        ids.sort();
+        let indices: Vec<_> = ids.iter().map(TerritoryId::index).collect();
+        write_items(&indices, out, len, count)
+    })
+}
+
+/// Writes the metadata of a territory to `out`.
+///
+/// # Safety
+///
+/// `out` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_territory_info(index: u16, out: *mut TtTerritoryInfo) -> TtStatus {
+    guard(|| {
+        if out.is_null() {
+            return Err(TtStatus::NullPointer);
+        }
+        let info = territory(index)?.info();
+
+        *out = TtTerritoryInfo {
+            sector: info.sector,
+            slots: info.slots,
+            db_id: info.db_id,
+            neighbor_count: info.neighbors.len(),
+        };
+        Ok(())
+    })
+}
+
+/// Writes the indices of the neighbors of a territory to `out` and their number to `count`.
+/// Returns [`TtStatus::BufferTooSmall`] if `len` is less than
+/// [`TtTerritoryInfo::neighbor_count`].
+///
+/// # Safety
+///
+/// `out` has to be valid for writes of `len` items and `count` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_territory_neighbors(
+    index: u16,
+    out: *mut u16,
+    len: usize,
+    count: *mut usize,
+) -> TtStatus {
+    guard(|| {
+        let neighbors: Vec<_> = territory(index)?
+            .info()
+            .neighbors
+            .iter()
+            .map(TerritoryId::index)
+            .collect();
+        write_items(&neighbors, out, len, count)
+    })
+}
+
+fn render_scale(scale: u32) -> Result<RenderScale, TtStatus> {
+    match scale {
+        1 => Ok(RenderScale::X1),
+        4 => Ok(RenderScale::X4),
+        _ => Err(TtStatus::InvalidScale),
+    }
+}
+
+/// Writes the size in bytes of the RGBA image that [`tt_render`] produces to `size`.
+///
+/// # Safety
+///
+/// `size` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_render_buffer_size(
+    viewport: TtViewport,
+    scale: u32,
+    size: *mut usize,
+) -> TtStatus {
+    guard(|| {
+        if size.is_null() {
+            return Err(TtStatus::NullPointer);
+        }
+        render_scale(scale)?;
+
+        *size = buffer_size(viewport, scale);
+        Ok(())
+    })
+}
+
+fn buffer_size(viewport: TtViewport, scale: u32) -> usize {
+    (viewport.width / scale) as usize * (viewport.height / scale) as usize * 4
+}
+
+fn layers(instructions: &[TtInstruction]) -> Result<Vec<RenderLayer>, TtStatus> {
+    instructions
+        .iter()
+        .map(|inst| {
+            Ok(RenderLayer {
+                instruction: RenderInstruction {
+                    colour: usvg::Color::new_rgb(inst.red, inst.green, inst.blue),
+                    opacity: inst.opacity,
+                },
+                territories: vec![territory(inst.territory)?],
+            })
+        })
+        .collect()
+}
+
+/// Renders the viewport with the map background into `buf` as 8 bit RGBA rows without padding.
+/// The image is `width / scale` by `height / scale` pixels, where `scale` is 1 or 4. Later
+/// instructions for the same territory take precedence.
+///
+/// # Safety
+///
+/// `fill` and `stroke` have to be valid for reads of `fill_len` and `stroke_len` items, they may
+/// be NULL if their length is 0. `buf` has to be valid for writes of `buf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_render(
+    viewport: TtViewport,
+    scale: u32,
+    fill: *const TtInstruction,
+    fill_len: usize,
+    stroke: *const TtInstruction,
+    stroke_len: usize,
+    buf: *mut u8,
+    buf_len: usize,
+) -> TtStatus {
+    guard(|| {
+        if buf.is_null() {
+            return Err(TtStatus::NullPointer);
+        }
+        let spec = RenderSpec {
+            viewport: Viewport::Rect {
+                x: viewport.x,
+                y: viewport.y,
+                width: viewport.width,
+                height: viewport.height,
+            },
+            scale: Some(render_scale(scale)?),
+            fill: layers(slice_from(fill, fill_len)?)?,
+            stroke: layers(slice_from(stroke, stroke_len)?)?,
+            format: OutputFormat::Png,
+        };
+
+        let size = buffer_size(viewport, scale);
+        if buf_len < size {
+            return Err(TtStatus::BufferTooSmall);
+        }
+
+        let image = TerritoryDataset::embedded()
+            .render_spec_image(&spec)
+            .map_err(|why| match why {
+                RenderError::UnknownTerritory(_) => TtStatus::UnknownTerritory,
+                RenderError::InvalidViewport => TtStatus::InvalidViewport,
+                RenderError::InvalidOpacity(_) => TtStatus::InvalidOpacity,
+                RenderError::Image(_) | RenderError::Tiles(_) | RenderError::EmptyAnimation => {
+                    TtStatus::Internal
+                }
+            })?;
+
+        slice::from_raw_parts_mut(buf, size).copy_from_slice(image.as_raw());
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+
+    #[test]
+    fn test_header_up_to_date() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/torn_territories.h"));
+        let committed = include_str!("../include/torn_territories.h");
+        assert!(
+            generated == committed,
+            "include/torn_territories.h is outdated, run `TT_UPDATE_HEADER=1 cargo build -p torn-territories-ffi`"
+        );
+    }
+
+    fn parse(id: &str) -> Result<u16, TtStatus> {
+        let id = CString::new(id).unwrap();
+        let mut index = 0;
+        match unsafe { tt_parse_id(id.as_ptr(), &mut index) } {
+            TtStatus::Ok => Ok(index),
+            status => Err(status),
+        }
+    }
+
+    #[test]
+    fn test_ids() {
+        let xod = parse(" xod").unwrap();
+        assert_eq!(xod, torn_territories::territory!("XOD").index());
+        assert_eq!(parse("ZZZ"), Err(TtStatus::InvalidId));
+        assert_eq!(
+            unsafe { tt_parse_id(std::ptr::null(), &mut 0) },
+            TtStatus::NullPointer
+        );
+
+        let mut buf = [1 as c_char; 4];
+        assert_eq!(unsafe { tt_id_to_str(xod, buf.as_mut_ptr()) }, TtStatus::Ok);
+        assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }, c"XOD");
+
+        let mut count = 0;
+        assert_eq!(
+            unsafe { tt_territory_ids(std::ptr::null_mut(), 0, &mut count) },
+            TtStatus::BufferTooSmall
+        );
+        assert_eq!(count, tt_territory_count());
+        let mut ids = vec![0; count];
+        assert_eq!(
+            unsafe { tt_territory_ids(ids.as_mut_ptr(), ids.len(), &mut count) },
+            TtStatus::Ok
+        );
+        assert!(ids.contains(&xod));
+    }
+
+    #[test]
+    fn test_info() {
+        let xod = parse("XOD").unwrap();
+        let expected = torn_territories::territory!("XOD").info();
+
+        let mut info = std::mem::MaybeUninit::uninit();
+        assert_eq!(
+            unsafe { tt_territory_info(xod, info.as_mut_ptr()) },
+            TtStatus::Ok
+        );
+        let info = unsafe { info.assume_init() };
+        assert_eq!(info.sector, expected.sector);
+        assert_eq!(info.slots, expected.slots);
+        assert_eq!(info.db_id, expected.db_id);
+        assert_eq!(info.neighbor_count, expected.neighbors.len());
+
+        let mut neighbors = vec![0; info.neighbor_count];
+        let mut count = 0;
+        assert_eq!(
+            unsafe { tt_territory_neighbors(xod, neighbors.as_mut_ptr(), 1, &mut count) },
+            TtStatus::BufferTooSmall
+        );
+        assert_eq!(
+            unsafe {
+                tt_territory_neighbors(xod, neighbors.as_mut_ptr(), neighbors.len(), &mut count)
+            },
+            TtStatus::Ok
+        );
+        assert_eq!(count, info.neighbor_count);
+        assert_eq!(neighbors[0], expected.neighbors[0].index());
+
+        assert_eq!(
+            unsafe { tt_territory_info(u16::MAX, &mut info.clone()) },
+            TtStatus::UnknownTerritory
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let viewport = TtViewport {
+            x: 100,
+            y: 100,
+            width: 64,
+            height: 48,
+        };
+        let fill = [TtInstruction {
+            territory: parse("XOD").unwrap(),
+            red: 255,
+            green: 0,
+            blue: 0,
+            opacity: 0.5,
+        }];
+
+        let mut size = 0;
+        assert_eq!(
+            unsafe { tt_render_buffer_size(viewport, 1, &mut size) },
+            TtStatus::Ok
+        );
+        assert_eq!(size, 64 * 48 * 4);
+
+        let mut buf = vec![0; size];
+        let render = |fill: &[TtInstruction], scale, buf: &mut [u8]| unsafe {
+            tt_render(
+                viewport,
+                scale,
+                fill.as_ptr(),
+                fill.len(),
+                std::ptr::null(),
+                0,
+                buf.as_mut_ptr(),
+                buf.len(),
+            )
+        };
+        assert_eq!(render(&fill, 1, &mut buf), TtStatus::Ok);
+        assert!(buf.iter().any(|b| *b != 0));
+
+        assert_eq!(render(&fill, 2, &mut buf), TtStatus::InvalidScale);
+        assert_eq!(
+            render(&fill, 1, &mut buf[..size - 1]),
+            TtStatus::BufferTooSmall
+        );
+
+        let invalid = [TtInstruction {
+            opacity: 1.5,
+            ..fill[0]
+        }];
+        assert_eq!(render(&invalid, 1, &mut buf), TtStatus::InvalidOpacity);
+
+        let invalid = [TtInstruction {
+            territory: u16::MAX,
+            ..fill[0]
+        }];
+        assert_eq!(render(&invalid, 1, &mut buf), TtStatus::UnknownTerritory);
+    }
+}
@@ -0,0 +1,310 @@
+//! Python bindings for `torn-territories`, built with maturin as the `torn_territories` module.
+//! `maturin develop -m torn-territories-py/Cargo.toml` installs it into the current virtualenv.
+//!
+//! View ports are `(x, y, width, height)` tuples in map pixels. Fill and stroke instructions map
+//! territory IDs to `(colour, opacity)` tuples, with colours as hex strings like `"#ff0000"`.
+
+use std::{collections::HashMap, io::Cursor};
+
+use image::{DynamicImage, ImageFormat};
+use numpy::{ndarray::Array, IntoPyArray, PyArray2, PyArray3};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use torn_territories::{
+    colour_from_hex, load_map_segment, OutputFormat, RenderInstruction, RenderLayer, RenderScale,
+    RenderSpec, TerritoryDataset, TerritoryId, Viewport, MAP_HEIGHT, MAP_WIDTH,
+};
+
+/// A territory of the embedded dataset.
+#[pyclass(
+    name = "Territory",
+    module = "torn_territories",
+    frozen,
+    eq,
+    hash,
+    from_py_object
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PyTerritory(TerritoryId);
+
+#[pymethods]
+impl PyTerritory {
+    /// Looks up a territory by its ID, ignoring case and surrounding whitespace.
+    #[new]
+    fn new(id: &str) -> PyResult<Self> {
+        Ok(Self(parse_id(id)?))
+    }
+
+    #[getter]
+    fn id(&self) -> &'static str {
+        self.0.as_str()
+    }
+
+    #[getter]
+    fn sector(&self) -> u8 {
+        self.0.info().sector
+    }
+
+    #[getter]
+    fn db_id(&self) -> i32 {
+        self.0.info().db_id
+    }
+
+    #[getter]
+    fn slots(&self) -> u16 {
+        self.0.info().slots
+    }
+
+    #[getter]
+    fn neighbors(&self) -> Vec<Self> {
+        self.0.info().neighbors.iter().map(|id| Self(*id)).collect()
+    }
+
+    /// The outline as an SVG path string in map coordinates.
+    #[getter]
+    fn svg_path(&self) -> String {
+        self.0.info().svg_path()
+    }
+
+    /// The bounding box of the outline as `(x, y, width, height)`.
+    #[getter]
+    fn bbox(&self) -> Option<(f32, f32, f32, f32)> {
+        let bounds = self.0.info().path()?.bounds();
+        Some((bounds.x(), bounds.y(), bounds.width(), bounds.height()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Territory('{}')", self.0)
+    }
+
+    fn __str__(&self) -> &'static str {
+        self.0.as_str()
+    }
+}
+
+fn parse_id(id: &str) -> PyResult<TerritoryId> {
+    TerritoryId::parse_lenient(id)
+        .map_err(|why: torn_territories::TerritoryIdError| PyValueError::new_err(why.to_string()))
+}
+
+/// All territories, sorted by ID.
+#[pyfunction]
+fn territories() -> Vec<PyTerritory> {
+    let mut ids: Vec<_> = TerritoryDataset::embedded().ids().collect();
+    ids.sort();
+    ids.into_iter().map(PyTerritory).collect()
+}
+
+/// The neighbor graph as an adjacency list, mapping each ID to the IDs of its neighbors.
+#[pyfunction]
+fn neighbor_graph() -> HashMap<&'static str, Vec<&'static str>> {
+    TerritoryDataset::embedded()
+        .iter()
+        .map(|(id, info)| {
+            (
+                id.as_str(),
+                info.neighbors.iter().map(|id| id.as_str()).collect(),
+            )
+        })
+        .collect()
+}
+
+/// Builds a spec from the arguments, `fill` and `stroke` map territory IDs to `(colour, opacity)`.
+fn spec(
+    view_port: (u32, u32, u32, u32),
+    fill: Option<HashMap<String, (String, f32)>>,
+    stroke: Option<HashMap<String, (String, f32)>>,
+    scale: &str,
+) -> PyResult<RenderSpec> {
+    let scale = match scale {
+        "x1" => RenderScale::X1,
+        "x4" => RenderScale::X4,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "unknown scale '{other}', expected 'x1' or 'x4'"
+            )))
+        }
+    };
+
+    let layers = |instructions: Option<HashMap<String, (String, f32)>>| {
+        instructions
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, (colour, opacity))| {
+                let colour = colour_from_hex(&colour).ok_or_else(|| {
+                    PyValueError::new_err(format!("'{colour}' is not a colour like '#ff0000'"))
+                })?;
+                Ok(RenderLayer {
+                    instruction: RenderInstruction { colour, opacity },
+                    territories: vec![parse_id(&id)?],
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()
+    };
+
+    let (x, y, width, height) = view_port;
+    Ok(RenderSpec {
+        viewport: Viewport::Rect {
+            x,
+            y,
+            width,
+            height,
+        },
+        scale: Some(scale),
+        fill: layers(fill)?,
+        stroke: layers(stroke)?,
+        format: OutputFormat::Png,
+    })
+}
+
+fn render(py: Python<'_>, spec: &RenderSpec) -> PyResult<image::RgbaImage> {
+    py.detach(|| TerritoryDataset::embedded().render_spec_image(spec))
+        .map_err(|why| PyValueError::new_err(why.to_string()))
+}
+
+fn encode_png(py: Python<'_>, image: DynamicImage) -> PyResult<Bound<'_, PyBytes>> {
+    let mut buf = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+        .map_err(|why| PyValueError::new_err(why.to_string()))?;
+    Ok(PyBytes::new(py, &buf))
+}
+
+/// Renders the view port `(x, y, width, height)` as an RGBA array of shape `(height, width, 4)`.
+#[pyfunction]
+#[pyo3(signature = (view_port, fill = None, stroke = None, scale = "x1"))]
+fn render_territories<'py>(
+    py: Python<'py>,
+    view_port: (u32, u32, u32, u32),
+    fill: Option<HashMap<String, (String, f32)>>,
+    stroke: Option<HashMap<String, (String, f32)>>,
+    scale: &str,
+) -> PyResult<Bound<'py, PyArray3<u8>>> {
+    let image = render(py, &spec(view_port, fill, stroke, scale)?)?;
+    let shape = (image.height() as usize, image.width() as usize, 4);
+    let array = Array::from_shape_vec(shape, image.into_raw())
+        .map_err(|why| PyValueError::new_err(why.to_string()))?;
+    Ok(array.into_pyarray(py))
+}
+
+/// Like `render_territories`, but returns the image as PNG bytes.
+#[pyfunction]
+#[pyo3(signature = (view_port, fill = None, stroke = None, scale = "x1"))]
+fn render_territories_png<'py>(
+    py: Python<'py>,
+    view_port: (u32, u32, u32, u32),
+    fill: Option<HashMap<String, (String, f32)>>,
+    stroke: Option<HashMap<String, (String, f32)>>,
+    scale: &str,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let image = render(py, &spec(view_port, fill, stroke, scale)?)?;
+    encode_png(py, DynamicImage::ImageRgba8(image))
+}
+
+fn segment(py: Python<'_>, view_port: (u32, u32, u32, u32)) -> PyResult<image::GrayImage> {
+    let (x, y, width, height) = view_port;
+    if width == 0
+        || height == 0
+        || x.saturating_add(width) > MAP_WIDTH
+        || y.saturating_add(height) > MAP_HEIGHT
+    {
+        return Err(PyValueError::new_err(
+            "viewport is empty or outside of the map",
+        ));
+    }
+
+    Ok(py.detach(|| load_map_segment(x, y, width, height)))
+}
+
+/// The greyscale map background of `(x, y, width, height)` as an array of shape
+/// `(height, width)`.
+#[pyfunction]
+fn load_map_segment_array<'py>(
+    py: Python<'py>,
+    view_port: (u32, u32, u32, u32),
+) -> PyResult<Bound<'py, PyArray2<u8>>> {
+    let image = segment(py, view_port)?;
+    let shape = (image.height() as usize, image.width() as usize);
+    let array = Array::from_shape_vec(shape, image.into_raw())
+        .map_err(|why| PyValueError::new_err(why.to_string()))?;
+    Ok(array.into_pyarray(py))
+}
+
+/// Like `load_map_segment`, but returns the image as PNG bytes.
+#[pyfunction]
+fn load_map_segment_png<'py>(
+    py: Python<'py>,
+    view_port: (u32, u32, u32, u32),
+) -> PyResult<Bound<'py, PyBytes>> {
+    let image = segment(py, view_port)?;
+    encode_png(py, DynamicImage::ImageLuma8(image))
+}
+
+#[pymodule(name = "torn_territories")]
+fn torn_territories_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTerritory>()?;
+    m.add_function(wrap_pyfunction!(territories, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbor_graph, m)?)?;
+    m.add_function(wrap_pyfunction!(render_territories, m)?)?;
+    m.add_function(wrap_pyfunction!(render_territories_png, m)?)?;
+    m.add(
+        "load_map_segment",
+        wrap_pyfunction!(load_map_segment_array, m)?,
+    )?;
+    m.add_function(wrap_pyfunction!(load_map_segment_png, m)?)?;
+    m.add("MAP_WIDTH", MAP_WIDTH)?;
+    m.add("MAP_HEIGHT", MAP_HEIGHT)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_territory() {
+        let xod = PyTerritory::new(" xod").unwrap();
+        assert_eq!(xod.id(), "XOD");
+        assert_eq!(xod.sector(), xod.0.info().sector);
+        assert!(xod.neighbors().iter().all(|n| n.neighbors().contains(&xod)));
+        assert!(xod.bbox().is_some());
+        assert_eq!(xod.__repr__(), "Territory('XOD')");
+
+        Python::attach(|py| {
+            let why = PyTerritory::new("ZZZ").unwrap_err();
+            assert!(why.is_instance_of::<PyValueError>(py));
+        });
+    }
+
+    #[test]
+    fn test_territories() {
+        let territories = territories();
+        assert_eq!(territories.len(), TerritoryDataset::embedded().len());
+        assert!(territories.windows(2).all(|w| w[0].0 < w[1].0));
+
+        let graph = neighbor_graph();
+        assert_eq!(graph.len(), territories.len());
+        assert!(graph["XOD"].iter().all(|n| graph[n].contains(&"XOD")));
+    }
+
+    #[test]
+    fn test_render_png() {
+        Python::attach(|py| {
+            let fill = HashMap::from([("XOD".to_owned(), ("#ff0000".to_owned(), 0.5))]);
+            let png =
+                render_territories_png(py, (100, 100, 64, 48), Some(fill), None, "x1").unwrap();
+            let image =
+                image::load_from_memory_with_format(png.as_bytes(), ImageFormat::Png).unwrap();
+            assert_eq!((image.width(), image.height()), (64, 48));
+
+            let fill = HashMap::from([("XOD".to_owned(), ("red".to_owned(), 0.5))]);
+            assert!(render_territories_png(py, (0, 0, 10, 10), Some(fill), None, "x1").is_err());
+            assert!(render_territories_png(py, (0, 0, 10, 10), None, None, "x2").is_err());
+
+            let png = load_map_segment_png(py, (10, 10, 30, 20)).unwrap();
+            let image =
+                image::load_from_memory_with_format(png.as_bytes(), ImageFormat::Png).unwrap();
+            assert_eq!((image.width(), image.height()), (30, 20));
+            assert!(load_map_segment_png(py, (MAP_WIDTH, 0, 1, 1)).is_err());
+        });
+    }
+}
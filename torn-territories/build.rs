@@ -1,5 +1,4 @@
 use std::{
-    collections::HashMap,
     env,
     fmt::Write as _,
     fs::File,
@@ -7,14 +6,12 @@ use std::{
     path::Path,
 };
 
-#[derive(serde::Deserialize)]
-struct Territory<'a> {
-    shape: &'a str,
-    db_id: i32,
-    sector: u8,
-    slots: u16,
-    neighbors: Option<Vec<&'a str>>,
-}
+#[path = "src/validation.rs"]
+mod validation;
+
+// Must be kept in sync with the constants in `src/lib.rs`.
+const MAP_WIDTH: u32 = 6_256;
+const MAP_HEIGHT: u32 = 3_648;
 
 #[derive(PartialEq, Eq, Hash)]
 pub struct TerritoryId([u8; 3]);
@@ -31,9 +28,77 @@ impl phf_shared::FmtConst for TerritoryId {
     }
 }
 
+/// Reads the registry of dense territory indices. The position of an id in the file is its index,
+/// so new ids may only ever be appended and removed ids have to stay in the file.
+fn read_index(ids: &[[u8; 3]]) -> Vec<Option<[u8; 3]>> {
+    let index = include_str!("./static/territory_index.txt");
+
+    let mut problems = Vec::new();
+    let mut seen = std::collections::HashMap::new();
+    for (line, id) in index.lines().enumerate() {
+        if !validation::is_valid_id(id) {
+            problems.push(format!(
+                "line {}: '{id}' is not a valid territory id",
+                line + 1
+            ));
+        } else if let Some(other) = seen.insert(id, line) {
+            problems.push(format!(
+                "line {}: '{id}' is already listed on line {}",
+                line + 1,
+                other + 1
+            ));
+        }
+    }
+
+    for id in ids {
+        let id = std::str::from_utf8(id).unwrap();
+        if !seen.contains_key(id) {
+            problems.push(format!(
+                "'{id}': territory has no index, append it to the end of the file"
+            ));
+        }
+    }
+
+    // the indices are stored as SMALLINT by the database integrations, see `src/index.rs`
+    if seen.len() > i16::MAX as usize {
+        problems.push("there are more ids than fit into an i16".to_owned());
+    }
+
+    if !problems.is_empty() {
+        panic!(
+            "territory_index.txt failed validation with {} problem(s):\n{}",
+            problems.len(),
+            problems.join("\n")
+        )
+    }
+
+    index
+        .lines()
+        .map(|id| {
+            let id: [u8; 3] = id.as_bytes().try_into().unwrap();
+            ids.contains(&id).then_some(id)
+        })
+        .collect()
+}
+
 fn main() {
-    let territories: HashMap<String, Territory> =
-        serde_json::from_slice(include_bytes!("./static/territory_shapes.json")).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/validation.rs");
+    println!("cargo:rerun-if-changed=static/territory_shapes.json");
+    println!("cargo:rerun-if-changed=static/territory_index.txt");
+
+    let validation::RawEntries(territories) =
+        serde_json::from_slice(include_bytes!("./static/territory_shapes.json"))
+            .unwrap_or_else(|why| panic!("territory_shapes.json could not be parsed: {why}"));
+
+    let mut shapes = validation::validate(&territories).unwrap_or_else(|problems| {
+        panic!(
+            "territory_shapes.json failed validation with {} problem(s):\n{}",
+            problems.len(),
+            problems.join("\n")
+        )
+    });
+
     let mut map = phf_codegen::Map::<TerritoryId>::new();
     let mut storage: Vec<([u8; 3], String)> = Vec::new();
 
@@ -41,8 +106,7 @@ fn main() {
         let mut path = "&[".to_owned();
         let mut first = true;
 
-        for segment in svgtypes::SimplifyingPathParser::from(tert.shape) {
-            let segment = segment.unwrap();
+        for segment in shapes.remove(&*id).unwrap() {
             if first {
                 first = false;
             } else {
@@ -51,24 +115,19 @@ fn main() {
 
             match segment {
                 svgtypes::SimplePathSegment::MoveTo { x, y } => {
-                    write!(
-                        path,
-                        "svgtypes::SimplePathSegment::MoveTo {{ x: {}f64, y: {}f64 }}",
-                        x, y
-                    )
-                    .unwrap();
+                    write!(path, "PathSegment::MoveTo {{ x: {}f64, y: {}f64 }}", x, y).unwrap();
                 }
                 svgtypes::SimplePathSegment::LineTo { x, y } => {
+                    write!(path, "PathSegment::LineTo {{ x: {}f64, y: {}f64 }}", x, y).unwrap();
+                }
+                svgtypes::SimplePathSegment::Quadratic { x1, y1, x, y } => {
                     write!(
                         path,
-                        "svgtypes::SimplePathSegment::LineTo {{ x: {}f64, y: {}f64 }}",
-                        x, y
+                        "PathSegment::Quadratic {{ x1: {}f64, y1: {}f64, x: {}f64, y: {}f64 }}",
+                        x1, y1, x, y
                     )
                     .unwrap();
                 }
-                svgtypes::SimplePathSegment::Quadratic { x1, y1, x, y } => {
-                    write!(path, "svgtypes::SimplePathSegment::Quadratic {{ x1: {}f64, y1: {}f64, x: {}f64, y: {}f64 }}", x1, y1, x, y).unwrap();
-                }
                 svgtypes::SimplePathSegment::CurveTo {
                     x1,
                     y1,
@@ -77,10 +136,10 @@ fn main() {
                     x,
                     y,
                 } => {
-                    write!(path, "svgtypes::SimplePathSegment::CurveTo {{ x1: {}f64, y1: {}f64, x2: {}f64, y2: {}f64, x: {}f64, y: {}f64 }}", x1, y1, x2, y2, x, y).unwrap();
+                    write!(path, "PathSegment::CurveTo {{ x1: {}f64, y1: {}f64, x2: {}f64, y2: {}f64, x: {}f64, y: {}f64 }}", x1, y1, x2, y2, x, y).unwrap();
                 }
                 svgtypes::SimplePathSegment::ClosePath => {
-                    write!(path, "svgtypes::SimplePathSegment::ClosePath").unwrap();
+                    write!(path, "PathSegment::ClosePath").unwrap();
                 }
             }
         }
@@ -100,12 +159,23 @@ fn main() {
         storage.push((
             id_bytes,
             format!(
-                "TerritoryInfo {{ sector: {}, db_id: {}, slots: {}, neighbors: &[{}], shape: {} }}",
+                "TerritoryInfo {{ sector: {}, db_id: {}, slots: {}, neighbors: alloc::borrow::Cow::Borrowed(&[{}]), shape: alloc::borrow::Cow::Borrowed({}) }}",
                 tert.sector, tert.db_id, tert.slots, neighbors, path
             ),
         ));
     }
 
+    let mut sorted_ids: Vec<_> = storage.iter().map(|(id, _)| *id).collect();
+    sorted_ids.sort();
+
+    let index = read_index(&sorted_ids);
+    let positions: std::collections::HashMap<_, _> = index
+        .iter()
+        .enumerate()
+        .filter_map(|(position, id)| Some((id.as_ref()?, position as u16)))
+        .collect();
+    let sorted_indices: Vec<_> = sorted_ids.iter().map(|id| positions[id]).collect();
+
     for (id, path) in storage {
         map.entry(TerritoryId(id), &path);
     }
@@ -119,4 +189,41 @@ fn main() {
         map.build()
     )
     .unwrap();
+
+    writeln!(
+        &mut file,
+        "const SORTED_TERRITORY_IDS: [[u8; 3]; {}] = {:?};",
+        sorted_ids.len(),
+        sorted_ids
+    )
+    .unwrap();
+
+    writeln!(
+        &mut file,
+        "const SORTED_TERRITORY_INDICES: [u16; {}] = {:?};",
+        sorted_indices.len(),
+        sorted_indices
+    )
+    .unwrap();
+
+    writeln!(
+        &mut file,
+        "const TERRITORY_INDEX_LEN: usize = {};",
+        index.len()
+    )
+    .unwrap();
+
+    writeln!(
+        &mut file,
+        "static TERRITORY_INDEX: [Option<TerritoryId>; TERRITORY_INDEX_LEN] = [{}];",
+        index
+            .iter()
+            .map(|id| match id {
+                Some(id) => format!("Some(TerritoryId({id:?}))"),
+                None => "None".to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+    .unwrap();
 }
@@ -0,0 +1,374 @@
+use std::{collections::BTreeMap, io::Write, time::Duration};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    error::{EncodingError, ImageFormatHint},
+    Delay, Frame, ImageError, ImageFormat, Rgba, RgbaImage,
+};
+
+#[cfg(feature = "embedded-tiles")]
+use crate::EmbeddedTiles;
+use crate::{
+    auto_colour,
+    render::render_background,
+    text::{draw_text, fill_rect, text_size},
+    OwnerId, OwnerStyle, OwnershipSnapshot, OwnershipTimeline, RenderError, RenderScale,
+    TerritoryDataset, TileSource, Timestamp,
+};
+
+const CAPTION_SCALE: u32 = 2;
+const CAPTION_PADDING: u32 = 6;
+
+/// Speed of the GIF colour quantisation, between 1 (best quality) and 30 (fastest).
+const GIF_SPEED: i32 = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    Apng,
+}
+
+/// One frame of an animation, with an optional caption in the lower left corner. Owners without
+/// a style in any of the frames get one automatically.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimationFrame {
+    pub snapshot: OwnershipSnapshot,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub caption: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationOptions {
+    pub format: AnimationFormat,
+    /// How long each frame is shown.
+    pub delay: Duration,
+    /// Draws a legend of the owners in the view port onto every frame.
+    pub legend: bool,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            format: AnimationFormat::Gif,
+            delay: Duration::from_secs(1),
+            legend: false,
+        }
+    }
+}
+
+impl AnimationFrame {
+    /// Frames of the ownership at each of the times, captioned with the time in UTC. Owners are
+    /// drawn in their [`auto_colour`].
+    ///
+    /// All frames are collected eagerly and each holds a snapshot of every territory in the
+    /// timeline, so callers should bound the number of `times`.
+    pub fn from_timeline(
+        timeline: &OwnershipTimeline,
+        times: impl IntoIterator<Item = Timestamp>,
+    ) -> Vec<Self> {
+        times
+            .into_iter()
+            .map(|at| Self {
+                snapshot: OwnershipSnapshot::new(timeline.snapshot_at(at)),
+                caption: Some(format_timestamp(at)),
+            })
+            .collect()
+    }
+}
+
+/// Formats the timestamp as `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_timestamp(at: Timestamp) -> String {
+    let days = at.div_euclid(86400);
+    let seconds = at.rem_euclid(86400);
+
+    // civil date from days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+/// Styles for every owner of any frame, so that owners keep their colour across the animation.
+/// The first style of an owner wins, owners without one get their [`auto_colour`].
+fn shared_styles(frames: &[AnimationFrame]) -> BTreeMap<OwnerId, OwnerStyle> {
+    let mut styles = BTreeMap::new();
+    for frame in frames {
+        for (owner, style) in &frame.snapshot.styles {
+            styles.entry(*owner).or_insert_with(|| style.clone());
+        }
+    }
+
+    for frame in frames {
+        for owner in frame.snapshot.owners.values() {
+            styles.entry(*owner).or_insert_with(|| OwnerStyle {
+                colour: auto_colour(*owner),
+                label: None,
+            });
+        }
+    }
+
+    styles
+}
+
+/// Renders the frames as an animation, see [`TerritoryDataset::render_animation`].
+#[cfg(feature = "embedded-tiles")]
+pub fn render_animation(
+    frames: &[AnimationFrame],
+    view_port: image::math::Rect,
+    scale: RenderScale,
+    options: &AnimationOptions,
+) -> Result<Vec<u8>, RenderError> {
+    TerritoryDataset::embedded().render_animation(frames, view_port, scale, options)
+}
+
+impl TerritoryDataset {
+    /// Renders the frames as an animated GIF or PNG. The map background is only loaded once and
+    /// owners keep the same colour in all frames.
+    #[cfg(feature = "embedded-tiles")]
+    pub fn render_animation(
+        &self,
+        frames: &[AnimationFrame],
+        view_port: image::math::Rect,
+        scale: RenderScale,
+        options: &AnimationOptions,
+    ) -> Result<Vec<u8>, RenderError> {
+        self.render_animation_with_tiles(&EmbeddedTiles, frames, view_port, scale, options)
+    }
+
+    /// Like [`TerritoryDataset::render_animation`], but loads the map background from `tiles`.
+    pub fn render_animation_with_tiles(
+        &self,
+        tiles: &(impl TileSource + ?Sized),
+        frames: &[AnimationFrame],
+        view_port: image::math::Rect,
+        scale: RenderScale,
+        options: &AnimationOptions,
+    ) -> Result<Vec<u8>, RenderError> {
+        if frames.is_empty() {
+            return Err(RenderError::EmptyAnimation);
+        }
+        if view_port.width < scale.factor() || view_port.height < scale.factor() {
+            return Err(RenderError::InvalidViewport);
+        }
+
+        let background = render_background(tiles, view_port, scale)?;
+        let styles = shared_styles(frames);
+        let images = frames.iter().map(|frame| {
+            let snapshot = OwnershipSnapshot {
+                owners: frame.snapshot.owners.clone(),
+                styles: styles.clone(),
+            };
+            let mut image = self.render_ownership_over(
+                &background,
+                &snapshot,
+                view_port,
+                scale,
+                options.legend,
+            );
+            if let Some(caption) = &frame.caption {
+                draw_caption(&mut image, caption);
+            }
+            image
+        });
+
+        let mut buf = Vec::new();
+        match options.format {
+            AnimationFormat::Gif => encode_gif(&mut buf, images, options.delay)?,
+            AnimationFormat::Apng => encode_apng(
+                &mut buf,
+                (background.width(), background.height()),
+                frames.len(),
+                images,
+                options.delay,
+            )?,
+        }
+
+        Ok(buf)
+    }
+}
+
+fn draw_caption(image: &mut RgbaImage, caption: &str) {
+    let (width, height) = text_size(caption, CAPTION_SCALE);
+    let y = image.height() as i64 - (height + 2 * CAPTION_PADDING) as i64;
+    fill_rect(
+        image,
+        0,
+        y,
+        width + 2 * CAPTION_PADDING,
+        height + 2 * CAPTION_PADDING,
+        Rgba([255, 255, 255, 210]),
+    );
+    draw_text(
+        image,
+        CAPTION_PADDING as i64,
+        y + CAPTION_PADDING as i64,
+        caption,
+        CAPTION_SCALE,
+        Rgba([0, 0, 0, 255]),
+    );
+}
+
+fn encode_gif(
+    writer: impl Write,
+    images: impl Iterator<Item = RgbaImage>,
+    delay: Duration,
+) -> Result<(), ImageError> {
+    let mut encoder = GifEncoder::new_with_speed(writer, GIF_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    for image in images {
+        let frame = Frame::from_parts(image, 0, 0, Delay::from_saturating_duration(delay));
+        encoder.encode_frame(frame)?;
+    }
+
+    Ok(())
+}
+
+fn encode_apng(
+    writer: impl Write,
+    (width, height): (u32, u32),
+    frame_count: usize,
+    images: impl Iterator<Item = RgbaImage>,
+    delay: Duration,
+) -> Result<(), ImageError> {
+    let error = |why: png::EncodingError| {
+        ImageError::Encoding(EncodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Png),
+            why,
+        ))
+    };
+
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frame_count as u32, 0).map_err(error)?;
+    let delay = delay.as_millis().min(u16::MAX as u128) as u16;
+    encoder.set_frame_delay(delay, 1000).map_err(error)?;
+
+    let mut writer = encoder.write_header().map_err(error)?;
+    for image in images {
+        writer.write_image_data(&image).map_err(error)?;
+    }
+    writer.finish().map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{territory, TerritoryMap};
+
+    fn frames() -> Vec<AnimationFrame> {
+        let mut timeline = OwnershipTimeline::new();
+        timeline.record(0, &TerritoryMap::from_iter([(territory!("XOD"), 2)]));
+        timeline.record(
+            3600,
+            &TerritoryMap::from_iter([(territory!("XOD"), 1), (territory!("VOD"), 2)]),
+        );
+        AnimationFrame::from_timeline(&timeline, [0, 3600])
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(1_792_330_260), "2026-10-18 13:31");
+        assert_eq!(format_timestamp(-60), "1969-12-31 23:59");
+    }
+
+    #[test]
+    fn test_shared_styles() {
+        let mut frames = frames();
+        // colours are stable even though owner 1 is missing from the first frame
+        assert_eq!(frames[0].snapshot.style(2).unwrap().colour, auto_colour(2));
+        assert_eq!(frames[0].caption.as_deref(), Some("1970-01-01 00:00"));
+
+        for frame in &mut frames {
+            frame.snapshot.styles.clear();
+        }
+        let styles = shared_styles(&frames);
+        assert_eq!(styles[&1].colour, auto_colour(1));
+        assert_eq!(styles[&2].colour, auto_colour(2));
+
+        let red = crate::colour_from_hex("#ff0000").unwrap();
+        frames[1].snapshot.styles.insert(
+            2,
+            OwnerStyle {
+                colour: red,
+                label: None,
+            },
+        );
+        assert_eq!(shared_styles(&frames)[&2].colour, red);
+    }
+
+    #[cfg(feature = "embedded-tiles")]
+    #[test]
+    fn test_render_animation() {
+        let view_port = image::math::Rect {
+            x: 100,
+            y: 100,
+            width: 64,
+            height: 48,
+        };
+        let mut options = AnimationOptions::default();
+
+        let gif = render_animation(&frames(), view_port, RenderScale::X1, &options).unwrap();
+        let decoder = image::codecs::gif::GifDecoder::new(gif.as_slice()).unwrap();
+        let decoded = image::AnimationDecoder::into_frames(decoder)
+            .collect_frames()
+            .unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].buffer().dimensions(), (64, 48));
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (1000, 1));
+
+        options.format = AnimationFormat::Apng;
+        options.delay = Duration::from_millis(250);
+        let apng = render_animation(&frames(), view_port, RenderScale::X1, &options).unwrap();
+        let decoder = png::Decoder::new(apng.as_slice());
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (64, 48));
+        assert_eq!(info.animation_control.unwrap().num_frames, 2);
+        assert_eq!(info.frame_control.unwrap().delay_num, 250);
+
+        assert!(matches!(
+            render_animation(&[], view_port, RenderScale::X1, &options),
+            Err(RenderError::EmptyAnimation)
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_frame_json() {
+        let frame: AnimationFrame = serde_json::from_str(
+            r##"{
+                "caption": "day 1",
+                "snapshot": { "owners": { "XOD": 1 }, "styles": { "1": { "colour": "#00ff00" } } }
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(frame.caption.as_deref(), Some("day 1"));
+        assert_eq!(frame.snapshot.owners[territory!("XOD")], 1);
+        assert_eq!(
+            frame.snapshot.style(1).unwrap().colour,
+            crate::colour_from_hex("#00ff00").unwrap()
+        );
+    }
+}
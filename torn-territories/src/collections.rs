@@ -0,0 +1,496 @@
+use alloc::boxed::Box;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Index, Sub, SubAssign};
+
+use crate::{TerritoryDataset, TerritoryId};
+
+const WORDS: usize = TerritoryId::INDEX_LEN.div_ceil(64);
+
+/// A set of territories stored as a fixed-size bitset over [`TerritoryId::index`].
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TerritorySet {
+    words: [u64; WORDS],
+}
+
+impl TerritorySet {
+    pub const fn new() -> Self {
+        Self { words: [0; WORDS] }
+    }
+
+    /// All territories of the dataset.
+    pub fn all(dataset: &TerritoryDataset) -> Self {
+        dataset.ids().collect()
+    }
+
+    fn position(id: TerritoryId) -> (usize, u64) {
+        let index = id.index() as usize;
+        (index / 64, 1 << (index % 64))
+    }
+
+    /// Returns whether the territory was newly inserted.
+    pub fn insert(&mut self, id: TerritoryId) -> bool {
+        let (word, bit) = Self::position(id);
+        let inserted = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        inserted
+    }
+
+    /// Returns whether the territory was part of the set.
+    pub fn remove(&mut self, id: TerritoryId) -> bool {
+        let (word, bit) = Self::position(id);
+        let removed = self.words[word] & bit != 0;
+        self.words[word] &= !bit;
+        removed
+    }
+
+    pub fn contains(&self, id: TerritoryId) -> bool {
+        let (word, bit) = Self::position(id);
+        self.words[word] & bit != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.words = [0; WORDS];
+    }
+
+    /// Iterates over the territories in order of their index.
+    pub fn iter(&self) -> SetIter<'_> {
+        SetIter {
+            words: &self.words,
+            word: 0,
+            current: self.words[0],
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.clone() | other
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.clone() & other
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.clone() - other
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .all(|(a, b)| a & !b == 0)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.words.iter().zip(&other.words).all(|(a, b)| a & b == 0)
+    }
+
+    /// Territories which border any territory of the set, without the set itself.
+    pub fn neighbors(&self, dataset: &TerritoryDataset) -> Self {
+        let mut neighbors = Self::new();
+        for id in self {
+            if let Some(info) = dataset.get(id) {
+                neighbors.extend(info.neighbors.iter().copied());
+            }
+        }
+
+        neighbors - self
+    }
+
+    /// The set together with all territories bordering it.
+    pub fn expand(&self, dataset: &TerritoryDataset) -> Self {
+        self.neighbors(dataset) | self
+    }
+}
+
+impl Default for TerritorySet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for TerritorySet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_set().entries(self).finish()
+    }
+}
+
+impl FromIterator<TerritoryId> for TerritorySet {
+    fn from_iter<T: IntoIterator<Item = TerritoryId>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<TerritoryId> for TerritorySet {
+    fn extend<T: IntoIterator<Item = TerritoryId>>(&mut self, iter: T) {
+        for id in iter {
+            self.insert(id);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a TerritorySet {
+    type Item = TerritoryId;
+    type IntoIter = SetIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+macro_rules! impl_set_op {
+    ($op:ident, $fn:ident, $assign_op:ident, $assign_fn:ident, $expr:expr) => {
+        impl $assign_op<&TerritorySet> for TerritorySet {
+            fn $assign_fn(&mut self, rhs: &TerritorySet) {
+                for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+                    *a = $expr(*a, *b);
+                }
+            }
+        }
+
+        impl $op<&TerritorySet> for TerritorySet {
+            type Output = TerritorySet;
+
+            fn $fn(mut self, rhs: &TerritorySet) -> Self::Output {
+                self.$assign_fn(rhs);
+                self
+            }
+        }
+
+        impl $op<TerritorySet> for TerritorySet {
+            type Output = TerritorySet;
+
+            fn $fn(self, rhs: TerritorySet) -> Self::Output {
+                self.$fn(&rhs)
+            }
+        }
+    };
+}
+
+impl_set_op!(BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+impl_set_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a & b);
+impl_set_op!(Sub, sub, SubAssign, sub_assign, |a: u64, b: u64| a & !b);
+
+pub struct SetIter<'a> {
+    words: &'a [u64; WORDS],
+    word: usize,
+    current: u64,
+}
+
+impl Iterator for SetIter<'_> {
+    type Item = TerritoryId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current != 0 {
+                let bit = self.current.trailing_zeros() as usize;
+                self.current &= self.current - 1;
+                // only ever set through valid ids
+                return TerritoryId::from_index((self.word * 64 + bit) as u16);
+            }
+
+            self.word += 1;
+            self.current = *self.words.get(self.word)?;
+        }
+    }
+}
+
+/// A map from territories to values, stored as a dense array over [`TerritoryId::index`].
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TerritoryMap<V> {
+    values: Box<[Option<V>]>,
+    len: usize,
+}
+
+impl<V> TerritoryMap<V> {
+    pub fn new() -> Self {
+        Self {
+            values: core::iter::repeat_with(|| None)
+                .take(TerritoryId::INDEX_LEN)
+                .collect(),
+            len: 0,
+        }
+    }
+
+    /// Returns the previous value of the territory.
+    pub fn insert(&mut self, id: TerritoryId, value: V) -> Option<V> {
+        let previous = self.values[id.index() as usize].replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    pub fn remove(&mut self, id: TerritoryId) -> Option<V> {
+        let previous = self.values[id.index() as usize].take();
+        if previous.is_some() {
+            self.len -= 1;
+        }
+        previous
+    }
+
+    pub fn get(&self, id: TerritoryId) -> Option<&V> {
+        self.values[id.index() as usize].as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: TerritoryId) -> Option<&mut V> {
+        self.values[id.index() as usize].as_mut()
+    }
+
+    pub fn contains_key(&self, id: TerritoryId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.values.iter_mut().for_each(|v| *v = None);
+        self.len = 0;
+    }
+
+    /// Iterates over the entries in order of the territory index.
+    pub fn iter(&self) -> impl Iterator<Item = (TerritoryId, &V)> + '_ {
+        self.values.iter().enumerate().filter_map(|(index, value)| {
+            Some((TerritoryId::from_index(index as u16)?, value.as_ref()?))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (TerritoryId, &mut V)> + '_ {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, value)| {
+                Some((TerritoryId::from_index(index as u16)?, value.as_mut()?))
+            })
+    }
+
+    pub fn keys(&self) -> TerritorySet {
+        self.iter().map(|(id, _)| id).collect()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.values.iter().filter_map(Option::as_ref)
+    }
+}
+
+impl<V> Default for TerritoryMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: core::fmt::Debug> core::fmt::Debug for TerritoryMap<V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> Index<TerritoryId> for TerritoryMap<V> {
+    type Output = V;
+
+    fn index(&self, id: TerritoryId) -> &Self::Output {
+        self.get(id).expect("territory is not part of the map")
+    }
+}
+
+impl<V> FromIterator<(TerritoryId, V)> for TerritoryMap<V> {
+    fn from_iter<T: IntoIterator<Item = (TerritoryId, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> Extend<(TerritoryId, V)> for TerritoryMap<V> {
+    fn extend<T: IntoIterator<Item = (TerritoryId, V)>>(&mut self, iter: T) {
+        for (id, value) in iter {
+            self.insert(id, value);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V, S> From<std::collections::HashMap<TerritoryId, V, S>> for TerritoryMap<V> {
+    fn from(value: std::collections::HashMap<TerritoryId, V, S>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl<V> IntoIterator for TerritoryMap<V> {
+    type Item = (TerritoryId, V);
+    type IntoIter = MapIntoIter<V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        MapIntoIter(self.values.into_vec().into_iter().enumerate())
+    }
+}
+
+pub struct MapIntoIter<V>(core::iter::Enumerate<alloc::vec::IntoIter<Option<V>>>);
+
+impl<V> Iterator for MapIntoIter<V> {
+    type Item = (TerritoryId, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .find_map(|(index, value)| Some((TerritoryId::from_index(index as u16)?, value?)))
+    }
+}
+
+/// Serialised as a sequence of territory ids.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TerritorySet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TerritorySet {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ids = alloc::vec::Vec::<TerritoryId>::deserialize(deserializer)?;
+        Ok(ids.into_iter().collect())
+    }
+}
+
+/// Serialised as a map keyed by territory id.
+#[cfg(feature = "serde")]
+impl<V: serde::Serialize> serde::Serialize for TerritoryMap<V> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, V: serde::Deserialize<'de>> serde::Deserialize<'de> for TerritoryMap<V> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapVisitor<V>(core::marker::PhantomData<V>);
+        impl<'de, V: serde::Deserialize<'de>> serde::de::Visitor<'de> for MapVisitor<V> {
+            type Value = TerritoryMap<V>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(formatter, "a map keyed by territory ids")
+            }
+
+            fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut map = TerritoryMap::new();
+                while let Some((id, value)) = access.next_entry()? {
+                    map.insert(id, value);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MapVisitor(core::marker::PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::territory;
+
+    #[test]
+    fn test_set_ops() {
+        let a: TerritorySet = [territory!("XOD"), territory!("GVE")].into_iter().collect();
+        let b: TerritorySet = [territory!("GVE"), territory!("AAB")].into_iter().collect();
+
+        assert_eq!(a.len(), 2);
+        assert!(a.contains(territory!("XOD")));
+        assert!(!a.contains(territory!("AAB")));
+
+        assert_eq!(a.union(&b).len(), 3);
+        assert_eq!(
+            a.intersection(&b).iter().collect::<Vec<_>>(),
+            vec![territory!("GVE")]
+        );
+        assert_eq!(
+            a.difference(&b).iter().collect::<Vec<_>>(),
+            vec![territory!("XOD")]
+        );
+        assert!(a.intersection(&b).is_subset(&a));
+        assert!(a.difference(&b).is_disjoint(&b));
+
+        let all = TerritorySet::all(TerritoryDataset::embedded());
+        assert_eq!(all.len(), TerritoryDataset::embedded().len());
+        assert_eq!(all.iter().count(), all.len());
+    }
+
+    #[test]
+    fn test_set_neighbors() {
+        let xod = territory!("XOD");
+        let set: TerritorySet = [xod].into_iter().collect();
+        let dataset = TerritoryDataset::embedded();
+
+        let neighbors = set.neighbors(dataset);
+        assert_eq!(neighbors.len(), xod.info().neighbors.len());
+        assert!(!neighbors.contains(xod));
+
+        let expanded = set.expand(dataset);
+        assert_eq!(expanded.len(), neighbors.len() + 1);
+        assert!(expanded.contains(xod));
+    }
+
+    #[test]
+    fn test_map() {
+        let mut map = TerritoryMap::new();
+        assert_eq!(map.insert(territory!("XOD"), 1), None);
+        assert_eq!(map.insert(territory!("GVE"), 2), None);
+        assert_eq!(map.insert(territory!("XOD"), 3), Some(1));
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[territory!("XOD")], 3);
+        assert_eq!(map.remove(territory!("GVE")), Some(2));
+        assert_eq!(map.len(), 1);
+        assert_eq!(
+            map.keys().iter().collect::<Vec<_>>(),
+            vec![territory!("XOD")]
+        );
+
+        #[cfg(feature = "std")]
+        {
+            let from_hash_map: TerritoryMap<_> =
+                std::collections::HashMap::from([(territory!("XOD"), 3)]).into();
+            assert_eq!(from_hash_map, map);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let map: TerritoryMap<u32> = serde_json::from_str(r#"{ "XOD": 1, "GVE": 2 }"#).unwrap();
+        assert_eq!(map[territory!("GVE")], 2);
+        assert!(serde_json::from_str::<TerritoryMap<u32>>(r#"{ "gve": 2 }"#).is_err());
+        let value = serde_json::to_value(&map).unwrap();
+        assert_eq!(value, serde_json::json!({ "XOD": 1, "GVE": 2 }));
+
+        let set: TerritorySet = serde_json::from_str(r#"["XOD", "GVE", "XOD"]"#).unwrap();
+        assert_eq!(set, map.keys());
+        assert_eq!(
+            serde_json::to_value(&set)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert!(serde_json::from_str::<TerritoryMap<u32>>(r#"{ "ZZZ": 1 }"#).is_err());
+    }
+}
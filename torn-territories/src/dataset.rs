@@ -0,0 +1,341 @@
+use alloc::{borrow::ToOwned, collections::BTreeMap, vec::Vec};
+
+use crate::{TerritoryId, TerritoryIdError, TerritoryInfo, TERRITORY_INFO};
+
+/// A set of territories and their metadata.
+///
+/// Either the dataset embedded at compile time (see [`TerritoryDataset::embedded`]) or one that was
+/// loaded at runtime, e.g. after Torn adjusted the shapes of some territories. Loaded datasets can
+/// only refer to territory ids which are known to this version of the crate.
+#[derive(Debug, Clone)]
+pub struct TerritoryDataset(Storage);
+
+#[derive(Debug, Clone)]
+enum Storage {
+    Embedded,
+    Loaded(BTreeMap<TerritoryId, TerritoryInfo>),
+}
+
+static EMBEDDED: TerritoryDataset = TerritoryDataset(Storage::Embedded);
+
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub enum DatasetError {
+    Json(serde_json::Error),
+    Invalid(Vec<String>),
+}
+
+#[cfg(feature = "json")]
+impl std::fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Json(why) => write!(f, "dataset could not be parsed: {why}"),
+            Self::Invalid(problems) => write!(
+                f,
+                "dataset failed validation with {} problem(s):\n{}",
+                problems.len(),
+                problems.join("\n")
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for DatasetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(why) => Some(why),
+            Self::Invalid(_) => None,
+        }
+    }
+}
+
+impl TerritoryDataset {
+    /// The dataset generated from `static/territory_shapes.json` at compile time.
+    pub fn embedded() -> &'static Self {
+        &EMBEDDED
+    }
+
+    /// Loads a dataset from JSON in the same schema as `territory_shapes.json`.
+    ///
+    /// The dataset goes through the same validation as the embedded one, and all problems are
+    /// reported at once.
+    ///
+    /// Territory ids are [`TerritoryId`]s, which only exist for the territories known to this
+    /// version of the crate. A dataset containing any other id is rejected with
+    /// [`DatasetError::Invalid`], so territories added by Torn still require a crate release.
+    /// Such datasets can be compared with [`crate::diff_json_datasets`] nonetheless.
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &[u8]) -> Result<Self, DatasetError> {
+        let crate::validation::RawEntries(entries) =
+            serde_json::from_slice(json).map_err(DatasetError::Json)?;
+        let mut shapes = crate::validation::validate(&entries).map_err(DatasetError::Invalid)?;
+
+        let mut problems = Vec::new();
+        let mut territories = Vec::with_capacity(entries.len());
+        for (key, raw) in entries {
+            let Ok(id) = key.parse::<TerritoryId>() else {
+                problems.push(format!(
+                    "'{key}': territory id is not known to this version of torn-territories"
+                ));
+                continue;
+            };
+
+            // the validation guarantees that all neighbors are keys of the dataset, so any unknown
+            // ones have already been reported above.
+            let neighbors = raw
+                .neighbors
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|n| n.parse().ok())
+                .collect::<Vec<_>>();
+
+            territories.push((
+                id,
+                TerritoryInfo {
+                    shape: shapes
+                        .remove(&*key)
+                        .unwrap()
+                        .into_iter()
+                        .map(crate::PathSegment::from)
+                        .collect(),
+                    sector: raw.sector,
+                    db_id: raw.db_id,
+                    slots: raw.slots,
+                    neighbors: neighbors.into(),
+                },
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(territories.into_iter().collect())
+        } else {
+            Err(DatasetError::Invalid(problems))
+        }
+    }
+
+    pub fn get(&self, id: TerritoryId) -> Option<&TerritoryInfo> {
+        match &self.0 {
+            Storage::Embedded => TERRITORY_INFO.get(&id),
+            Storage::Loaded(territories) => territories.get(&id),
+        }
+    }
+
+    pub fn contains(&self, id: TerritoryId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Storage::Embedded => TERRITORY_INFO.len(),
+            Storage::Loaded(territories) => territories.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Parses a territory id typed by a user and checks that it is part of this dataset, see
+    /// [`TerritoryId::parse_lenient`].
+    pub fn parse_id(&self, s: &str) -> Result<TerritoryId, TerritoryIdError> {
+        match s.trim().to_ascii_uppercase().parse::<TerritoryId>() {
+            Ok(id) if self.contains(id) => Ok(id),
+            Ok(_) | Err(TerritoryIdError::DoesNotExist { .. }) => {
+                Err(TerritoryIdError::DoesNotExist {
+                    input: s.trim().to_owned(),
+                    suggestions: self.closest_ids(s, crate::MAX_SUGGESTIONS),
+                })
+            }
+            Err(why) => Err(why),
+        }
+    }
+
+    /// Up to `limit` ids of this dataset which are the fewest edits away from `s`, ignoring case.
+    /// Ids that have nothing in common with `s` are never suggested.
+    pub fn closest_ids(&self, s: &str, limit: usize) -> Vec<TerritoryId> {
+        let input = s.trim().to_ascii_uppercase();
+        let max_distance = input.len().min(3).saturating_sub(1);
+
+        // among equally distant ids, the ones with the same letters in a different order are the
+        // most likely to have been meant.
+        let mut candidates: Vec<_> = self
+            .ids()
+            .map(|id| {
+                let distance = crate::edit_distance(input.as_bytes(), &id.0);
+                let mut letters = id.0.to_vec();
+                let missing = input
+                    .bytes()
+                    .filter(|b| match letters.iter().position(|l| l == b) {
+                        Some(pos) => {
+                            letters.swap_remove(pos);
+                            false
+                        }
+                        None => true,
+                    })
+                    .count();
+                (distance, missing, id)
+            })
+            .filter(|(distance, _, _)| *distance <= max_distance)
+            .collect();
+        candidates.sort();
+
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, id)| id)
+            .collect()
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        match &self.0 {
+            Storage::Embedded => Iter(IterInner::Embedded(TERRITORY_INFO.entries())),
+            Storage::Loaded(territories) => Iter(IterInner::Loaded(territories.iter())),
+        }
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = TerritoryId> + '_ {
+        self.iter().map(|(id, _)| id)
+    }
+}
+
+impl Default for TerritoryDataset {
+    fn default() -> Self {
+        EMBEDDED.clone()
+    }
+}
+
+/// Builds a dataset from territories without validating them.
+impl FromIterator<(TerritoryId, TerritoryInfo)> for TerritoryDataset {
+    fn from_iter<T: IntoIterator<Item = (TerritoryId, TerritoryInfo)>>(iter: T) -> Self {
+        Self(Storage::Loaded(iter.into_iter().collect()))
+    }
+}
+
+/// Serialised in the schema of `territory_shapes.json`, sorted by id.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TerritoryDataset {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut territories: Vec<_> = self.iter().collect();
+        territories.sort_by_key(|(id, _)| *id);
+
+        serializer.collect_map(territories)
+    }
+}
+
+impl<'a> IntoIterator for &'a TerritoryDataset {
+    type Item = (TerritoryId, &'a TerritoryInfo);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a>(IterInner<'a>);
+
+enum IterInner<'a> {
+    Embedded(phf::map::Entries<'static, TerritoryId, TerritoryInfo>),
+    Loaded(alloc::collections::btree_map::Iter<'a, TerritoryId, TerritoryInfo>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (TerritoryId, &'a TerritoryInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterInner::Embedded(entries) => entries.next().map(|(id, info)| (*id, info)),
+            IterInner::Loaded(entries) => entries.next().map(|(id, info)| (*id, info)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterInner::Embedded(entries) => entries.size_hint(),
+            IterInner::Loaded(entries) => entries.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded() {
+        let dataset = TerritoryDataset::embedded();
+        let id: TerritoryId = "XOD".parse().unwrap();
+
+        assert_eq!(dataset.len(), TERRITORY_INFO.len());
+        assert_eq!(dataset.iter().count(), dataset.len());
+        assert_eq!(dataset.get(id).unwrap().db_id, id.info().db_id);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_from_json() {
+        let dataset =
+            TerritoryDataset::from_json(include_bytes!("../static/territory_shapes.json")).unwrap();
+
+        assert_eq!(dataset.len(), TERRITORY_INFO.len());
+        for (id, info) in TerritoryDataset::embedded() {
+            let loaded = dataset.get(id).unwrap();
+            assert_eq!(loaded.db_id, info.db_id);
+            assert_eq!(loaded.neighbors, info.neighbors);
+            assert_eq!(loaded.shape, info.shape);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_roundtrip() {
+        let json = serde_json::to_vec(TerritoryDataset::embedded()).unwrap();
+        let dataset = TerritoryDataset::from_json(&json).unwrap();
+
+        assert_eq!(dataset.len(), TERRITORY_INFO.len());
+        for (id, info) in TerritoryDataset::embedded() {
+            assert_eq!(dataset.get(id).unwrap().shape, info.shape);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_from_json_invalid() {
+        let json = br#"{
+            "XOD": { "shape": "M0,0L10,0L10,10z", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["GVE"] },
+            "GVE": { "shape": "M0,0L10,0L10,10", "db_id": 1, "sector": 1, "slots": 1 },
+            "QQQ": { "shape": "M0,0L10,0L10,10z", "db_id": 2, "sector": 1, "slots": 1 }
+        }"#;
+
+        let Err(DatasetError::Invalid(problems)) = TerritoryDataset::from_json(json) else {
+            panic!("dataset should be invalid");
+        };
+        assert_eq!(problems.len(), 3);
+
+        let json =
+            br#"{ "QQQ": { "shape": "M0,0L10,0L10,10z", "db_id": 2, "sector": 1, "slots": 1 } }"#;
+        let Err(DatasetError::Invalid(problems)) = TerritoryDataset::from_json(json) else {
+            panic!("dataset should be invalid");
+        };
+        assert_eq!(problems.len(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_from_json_escapes() {
+        let json = br#"{
+            "\u0058OD": { "shape": "M0,0\u004c10,0L10,10z", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["G\u0056E"] },
+            "GVE": { "shape": "M0,0L10,0L10,10z", "db_id": 2, "sector": 1, "slots": 1, "neighbors": ["XOD"] }
+        }"#;
+
+        let dataset = TerritoryDataset::from_json(json).unwrap();
+        let info = dataset.get(crate::territory!("XOD")).unwrap();
+        assert_eq!(info.shape.len(), 4);
+        assert_eq!(&*info.neighbors, &[crate::territory!("GVE")]);
+    }
+}
@@ -0,0 +1,474 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use crate::{PathSegment, TerritoryDataset};
+
+/// Differences between two versions of the territory data.
+///
+/// Territories are identified by the keys of the datasets rather than by [`crate::TerritoryId`],
+/// so that territories which are unknown to this version of the crate can be reported as well.
+#[derive(Debug, Clone, Default)]
+pub struct DatasetDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<TerritoryChange>,
+    /// Shapes of the reported territories, from the old dataset for removed ones.
+    outlines: BTreeMap<String, Vec<PathSegment>>,
+}
+
+/// Changes to a territory which exists in both datasets. Fields which did not change are `None`
+/// or empty.
+#[derive(Debug, Clone)]
+pub struct TerritoryChange {
+    pub id: String,
+    pub sector: Option<(u8, u8)>,
+    pub db_id: Option<(i32, i32)>,
+    pub slots: Option<(u16, u16)>,
+    pub neighbors_added: Vec<String>,
+    pub neighbors_removed: Vec<String>,
+    /// Largest distance in map pixels that a point of either outline is away from the other one.
+    pub geometry: Option<f64>,
+}
+
+/// A territory of either dataset, independent of how the dataset was loaded.
+struct Entry<'a> {
+    shape: Cow<'a, [PathSegment]>,
+    sector: u8,
+    db_id: i32,
+    slots: u16,
+    neighbors: Vec<Cow<'a, str>>,
+}
+
+impl DatasetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// All territories that were added, removed or changed.
+    pub fn territories(&self) -> impl Iterator<Item = &str> + '_ {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .chain(self.changed.iter().map(|c| &c.id))
+            .map(String::as_str)
+    }
+
+    /// The shape of a reported territory in the new dataset, or in the old one if it was removed.
+    pub fn outline(&self, id: &str) -> Option<&[PathSegment]> {
+        self.outlines.get(id).map(Vec::as_slice)
+    }
+}
+
+impl TerritoryChange {
+    fn is_empty(&self) -> bool {
+        self.sector.is_none()
+            && self.db_id.is_none()
+            && self.slots.is_none()
+            && self.neighbors_added.is_empty()
+            && self.neighbors_removed.is_empty()
+            && self.geometry.is_none()
+    }
+}
+
+/// Compares two datasets. Shape changes are only reported if the outlines are further than
+/// `geometry_threshold` map pixels apart.
+pub fn diff_datasets(
+    old: &TerritoryDataset,
+    new: &TerritoryDataset,
+    geometry_threshold: f64,
+) -> DatasetDiff {
+    fn entries(dataset: &TerritoryDataset) -> BTreeMap<String, Entry<'_>> {
+        dataset
+            .iter()
+            .map(|(id, info)| {
+                let entry = Entry {
+                    shape: Cow::Borrowed(&info.shape),
+                    sector: info.sector,
+                    db_id: info.db_id,
+                    slots: info.slots,
+                    neighbors: info
+                        .neighbors
+                        .iter()
+                        .map(|n| Cow::Borrowed(n.as_str()))
+                        .collect(),
+                };
+                (id.to_string(), entry)
+            })
+            .collect()
+    }
+
+    diff_entries(entries(old), entries(new), geometry_threshold)
+}
+
+/// Compares two datasets in the schema of `territory_shapes.json`, like [`diff_datasets`].
+///
+/// Both datasets are validated like [`TerritoryDataset::from_json`], but unlike it they may
+/// contain territory ids which are unknown to this version of the crate, so that new dumps can be
+/// compared before the crate is updated.
+#[cfg(feature = "json")]
+pub fn diff_json_datasets(
+    old: &[u8],
+    new: &[u8],
+    geometry_threshold: f64,
+) -> Result<DatasetDiff, crate::DatasetError> {
+    fn entries(json: &[u8]) -> Result<BTreeMap<String, Entry<'_>>, crate::DatasetError> {
+        let crate::validation::RawEntries(entries) =
+            serde_json::from_slice(json).map_err(crate::DatasetError::Json)?;
+        let mut shapes =
+            crate::validation::validate(&entries).map_err(crate::DatasetError::Invalid)?;
+
+        // the validation guarantees that the keys are unique and that all shapes could be parsed
+        Ok(entries
+            .into_iter()
+            .map(|(id, raw)| {
+                let entry = Entry {
+                    shape: shapes
+                        .remove(&*id)
+                        .unwrap()
+                        .into_iter()
+                        .map(PathSegment::from)
+                        .collect(),
+                    sector: raw.sector,
+                    db_id: raw.db_id,
+                    slots: raw.slots,
+                    neighbors: raw.neighbors.unwrap_or_default(),
+                };
+                (id.into_owned(), entry)
+            })
+            .collect())
+    }
+
+    Ok(diff_entries(
+        entries(old)?,
+        entries(new)?,
+        geometry_threshold,
+    ))
+}
+
+fn diff_entries(
+    old: BTreeMap<String, Entry>,
+    mut new: BTreeMap<String, Entry>,
+    geometry_threshold: f64,
+) -> DatasetDiff {
+    let mut diff = DatasetDiff::default();
+
+    for (id, old_entry) in old {
+        match new.remove(&id) {
+            None => {
+                diff.outlines
+                    .insert(id.clone(), old_entry.shape.into_owned());
+                diff.removed.push(id);
+            }
+            Some(new_entry) => {
+                let change = diff_territory(id, &old_entry, &new_entry, geometry_threshold);
+                if !change.is_empty() {
+                    diff.outlines
+                        .insert(change.id.clone(), new_entry.shape.into_owned());
+                    diff.changed.push(change);
+                }
+            }
+        }
+    }
+
+    // everything that is left was not part of the old dataset
+    for (id, new_entry) in new {
+        diff.outlines
+            .insert(id.clone(), new_entry.shape.into_owned());
+        diff.added.push(id);
+    }
+
+    diff
+}
+
+fn diff_territory(
+    id: String,
+    old: &Entry,
+    new: &Entry,
+    geometry_threshold: f64,
+) -> TerritoryChange {
+    fn changed<T: PartialEq + Copy>(old: T, new: T) -> Option<(T, T)> {
+        (old != new).then_some((old, new))
+    }
+
+    fn missing(from: &[Cow<str>], other: &[Cow<str>]) -> Vec<String> {
+        let mut missing: Vec<_> = from
+            .iter()
+            .filter(|n| !other.contains(n))
+            .map(|n| n.to_string())
+            .collect();
+        missing.sort();
+        missing
+    }
+
+    let geometry = if old.shape == new.shape {
+        None
+    } else {
+        Some(outline_distance(&old.shape, &new.shape)).filter(|d| *d > geometry_threshold)
+    };
+
+    TerritoryChange {
+        id,
+        sector: changed(old.sector, new.sector),
+        db_id: changed(old.db_id, new.db_id),
+        slots: changed(old.slots, new.slots),
+        neighbors_added: missing(&new.neighbors, &old.neighbors),
+        neighbors_removed: missing(&old.neighbors, &new.neighbors),
+        geometry,
+    }
+}
+
+fn outline_points(shape: &[PathSegment]) -> Vec<(f64, f64)> {
+    shape
+        .iter()
+        .filter_map(|segment| match *segment {
+            PathSegment::MoveTo { x, y }
+            | PathSegment::LineTo { x, y }
+            | PathSegment::Quadratic { x, y, .. }
+            | PathSegment::CurveTo { x, y, .. } => Some((x, y)),
+            PathSegment::ClosePath => None,
+        })
+        .collect()
+}
+
+/// Hausdorff distance between the vertices of both outlines.
+fn outline_distance(a: &[PathSegment], b: &[PathSegment]) -> f64 {
+    fn directed(from: &[(f64, f64)], to: &[(f64, f64)]) -> f64 {
+        from.iter()
+            .map(|(x1, y1)| {
+                to.iter()
+                    .map(|(x2, y2)| (x1 - x2).hypot(y1 - y2))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0f64, f64::max)
+    }
+
+    let a = outline_points(a);
+    let b = outline_points(b);
+
+    directed(&a, &b).max(directed(&b, &a))
+}
+
+/// Added territories are highlighted in green, removed ones in red and changed ones in orange.
+#[cfg(feature = "render")]
+impl DatasetDiff {
+    #[cfg(feature = "embedded-tiles")]
+    pub fn render(
+        &self,
+        view_port: image::math::Rect,
+        scale: crate::RenderScale,
+    ) -> image::RgbaImage {
+        // the embedded tiles are complete, see `TileSource`
+        self.render_with_tiles(&crate::EmbeddedTiles, view_port, scale)
+            .unwrap()
+    }
+
+    /// Like [`DatasetDiff::render`], but loads the map background from `tiles`.
+    pub fn render_with_tiles(
+        &self,
+        tiles: &(impl crate::TileSource + ?Sized),
+        view_port: image::math::Rect,
+        scale: crate::RenderScale,
+    ) -> Result<image::RgbaImage, crate::TileError> {
+        let highlight = |hex| {
+            crate::render::fill_for(&crate::RenderInstruction {
+                colour: crate::colour_from_hex(hex).unwrap(),
+                opacity: 0.7,
+            })
+        };
+
+        let highlighted = self
+            .added
+            .iter()
+            .map(|id| (id, "#2ecc40"))
+            .chain(self.removed.iter().map(|id| (id, "#ff4136")))
+            .chain(self.changed.iter().map(|c| (&c.id, "#ff851b")));
+
+        let elements = highlighted.filter_map(|(id, colour)| {
+            let path = crate::geometry::path_for_shape(self.outline(id)?)?;
+            Some(crate::render::element_for_path(
+                path,
+                Some(highlight(colour)),
+                None,
+            ))
+        });
+
+        let mut background = crate::render::render_background(tiles, view_port, scale)?;
+        let shapes = crate::render::render_elements(elements, view_port, scale);
+        image::imageops::overlay(&mut background, &shapes, 0, 0);
+
+        Ok(background)
+    }
+}
+
+impl std::fmt::Display for DatasetDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn list(ids: &[String]) -> String {
+            ids.join(", ")
+        }
+
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+
+        if !self.added.is_empty() {
+            writeln!(f, "Added ({}): {}", self.added.len(), list(&self.added))?;
+        }
+        if !self.removed.is_empty() {
+            writeln!(
+                f,
+                "Removed ({}): {}",
+                self.removed.len(),
+                list(&self.removed)
+            )?;
+        }
+        if !self.changed.is_empty() {
+            writeln!(f, "Changed ({}):", self.changed.len())?;
+        }
+
+        for change in &self.changed {
+            writeln!(f, "  {}:", change.id)?;
+            if let Some((old, new)) = change.sector {
+                writeln!(f, "    sector: {old} -> {new}")?;
+            }
+            if let Some((old, new)) = change.db_id {
+                writeln!(f, "    db_id: {old} -> {new}")?;
+            }
+            if let Some((old, new)) = change.slots {
+                writeln!(f, "    slots: {old} -> {new}")?;
+            }
+            if !change.neighbors_added.is_empty() {
+                writeln!(f, "    neighbors added: {}", list(&change.neighbors_added))?;
+            }
+            if !change.neighbors_removed.is_empty() {
+                writeln!(
+                    f,
+                    "    neighbors removed: {}",
+                    list(&change.neighbors_removed)
+                )?;
+            }
+            if let Some(distance) = change.geometry {
+                writeln!(f, "    shape moved by up to {distance:.1}px")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TerritoryId;
+
+    #[test]
+    fn test_diff() {
+        let embedded = TerritoryDataset::embedded();
+        let xod: TerritoryId = "XOD".parse().unwrap();
+        let gve: TerritoryId = "GVE".parse().unwrap();
+
+        let modified: TerritoryDataset = embedded
+            .iter()
+            .filter(|(id, _)| *id != gve)
+            .map(|(id, info)| {
+                let mut info = info.clone();
+                if id == xod {
+                    info.slots += 1;
+                    info.neighbors.to_mut().pop();
+                    info.shape = info
+                        .shape
+                        .iter()
+                        .map(|segment| match *segment {
+                            PathSegment::MoveTo { x, y } => PathSegment::MoveTo { x: x + 10f64, y },
+                            other => other,
+                        })
+                        .collect();
+                }
+                (id, info)
+            })
+            .collect();
+
+        let diff = diff_datasets(embedded, &modified, 1f64);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed, vec!["GVE"]);
+        assert_eq!(diff.changed.len(), 1);
+
+        let change = &diff.changed[0];
+        assert_eq!(change.id, "XOD");
+        assert_eq!(change.slots, Some((xod.info().slots, xod.info().slots + 1)));
+        assert_eq!(change.neighbors_removed.len(), 1);
+        assert!(change.geometry.unwrap() > 1f64);
+
+        let diff = diff_datasets(embedded, &modified, 100f64);
+        assert!(diff.changed[0].geometry.is_none());
+
+        assert!(diff_datasets(embedded, embedded, 0f64).is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_diff_json() {
+        let old = serde_json::to_vec(TerritoryDataset::embedded()).unwrap();
+
+        // GVE is removed together with the references to it, and ZZZ is not known to the crate
+        let mut json: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_slice(&old).unwrap();
+        json.remove("GVE").unwrap();
+        for territory in json.values_mut() {
+            if let Some(neighbors) = territory["neighbors"].as_array_mut() {
+                neighbors.retain(|n| n != "GVE");
+            }
+        }
+        json.insert(
+            "ZZZ".to_owned(),
+            serde_json::json!({
+                "shape": "M 100 100 L 200 100 L 200 200 L 100 200 Z",
+                "db_id": -1,
+                "sector": 1,
+                "slots": 1
+            }),
+        );
+        let new = serde_json::to_vec(&json).unwrap();
+
+        let diff = diff_json_datasets(&old, &new, 1f64).unwrap();
+        assert_eq!(diff.added, vec!["ZZZ"]);
+        assert_eq!(diff.removed, vec!["GVE"]);
+        assert!(diff
+            .changed
+            .iter()
+            .all(|c| c.neighbors_removed == vec!["GVE"] && c.neighbors_added.is_empty()));
+        assert_eq!(
+            diff.changed.len(),
+            "GVE".parse::<TerritoryId>().unwrap().info().neighbors.len()
+        );
+        assert_eq!(diff.outline("ZZZ").unwrap().len(), 5);
+
+        assert!(diff_json_datasets(&old, &old, 0f64).unwrap().is_empty());
+        assert!(matches!(
+            diff_json_datasets(&old, b"{}}", 0f64),
+            Err(crate::DatasetError::Json(_))
+        ));
+    }
+
+    #[cfg(all(feature = "json", feature = "embedded-tiles"))]
+    #[test]
+    fn test_render_diff() {
+        let old = br#"{ "XOD": { "shape": "M 0 0 L 50 0 L 50 50 Z", "db_id": 1, "sector": 1, "slots": 1 } }"#;
+        let new = br#"{ "ZZZ": { "shape": "M 100 100 L 200 100 L 200 200 L 100 200 Z", "db_id": 1, "sector": 1, "slots": 1 } }"#;
+        let diff = diff_json_datasets(old, new, 1f64).unwrap();
+
+        let view_port = image::math::Rect {
+            x: 0,
+            y: 0,
+            width: 300,
+            height: 300,
+        };
+        let plain = crate::load_map_segment(0, 0, 300, 300);
+        let image = diff.render(view_port, crate::RenderScale::X1);
+
+        // the added territory is green and the removed one red, even though ZZZ has no id
+        let added = image.get_pixel(150, 150);
+        assert!(added[1] > added[0] && added[1] > added[2]);
+        let removed = image.get_pixel(40, 10);
+        assert!(removed[0] > removed[1] && removed[0] > removed[2]);
+        assert_eq!(image.get_pixel(250, 250)[0], plain.get_pixel(250, 250)[0]);
+    }
+}
@@ -0,0 +1,446 @@
+use image::{imageops::overlay, Rgba, RgbaImage};
+use tiny_skia_path::Rect;
+
+use crate::{RenderScale, TerritoryDataset, TerritoryId, MAP_HEIGHT, MAP_WIDTH};
+
+/// Space around the framed territories, on every side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Padding {
+    /// Map pixels.
+    Pixels(f32),
+    /// Percent of the width and height of the framed territories.
+    Percent(f32),
+}
+
+/// What happens if the frame reaches past the edge of the map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum EdgePolicy {
+    /// Cuts off the part outside of the map, the frame gets smaller and loses its aspect ratio.
+    Clamp,
+    /// Moves the frame back onto the map, so the territories are no longer centred. Frames that
+    /// are larger than the map are clamped.
+    #[default]
+    Shift,
+    /// Keeps the frame centred and pads the part outside of the map, see [`ViewFrame::pad`].
+    Pad,
+}
+
+/// How [`TerritoryDataset::frame_territories`] frames territories. Sizes are in map pixels.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Framing {
+    pub padding: Padding,
+    /// Width divided by height, the frame is widened or heightened to match it.
+    pub aspect_ratio: Option<f32>,
+    /// Frames are enlarged around their centre to at least this size, so small territories are
+    /// not zoomed in too far.
+    pub min_size: Option<(f32, f32)>,
+    /// Frames are shrunk around their centre to at most this size, which can cut off parts of
+    /// the territories.
+    pub max_size: Option<(f32, f32)>,
+    pub edge_policy: EdgePolicy,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            padding: Padding::Percent(10f32),
+            aspect_ratio: Some(4f32 / 3f32),
+            min_size: Some((320f32, 240f32)),
+            max_size: None,
+            edge_policy: EdgePolicy::Shift,
+        }
+    }
+}
+
+/// A frame around territories. The frame only extends past the map with [`EdgePolicy::Pad`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewFrame {
+    /// The part of the frame on the map, which can be passed to e.g.
+    /// [`TerritoryDataset::render_territories`].
+    pub view_port: image::math::Rect,
+    /// Size of the whole frame in map pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Position of the view port inside of the frame in map pixels.
+    pub offset: (u32, u32),
+}
+
+impl Framing {
+    /// Frames the bounds, which are in map pixels. `None` if the padding is not finite or
+    /// negative enough to swallow the bounds, if the aspect ratio or a size is not finite and
+    /// positive, or if the frame would grow beyond `i32::MAX` pixels.
+    pub fn frame(&self, bounds: Rect) -> Option<ViewFrame> {
+        let is_positive = |value: f32| value.is_finite() && value > 0f32;
+        if !self.aspect_ratio.is_none_or(is_positive)
+            || !self
+                .min_size
+                .is_none_or(|(w, h)| is_positive(w) && is_positive(h))
+            || !self
+                .max_size
+                .is_none_or(|(w, h)| is_positive(w) && is_positive(h))
+        {
+            return None;
+        }
+
+        let (mut width, mut height) = match self.padding {
+            Padding::Pixels(padding) => (
+                bounds.width() + 2f32 * padding,
+                bounds.height() + 2f32 * padding,
+            ),
+            Padding::Percent(percent) => (
+                bounds.width() * (1f32 + 2f32 * percent / 100f32),
+                bounds.height() * (1f32 + 2f32 * percent / 100f32),
+            ),
+        };
+        // also rejects a NaN or infinite padding
+        if !(width.is_finite() && width >= 0f32 && height.is_finite() && height >= 0f32) {
+            return None;
+        }
+        width = width.max(1f32);
+        height = height.max(1f32);
+
+        if let Some(ar) = self.aspect_ratio {
+            if width / height > ar {
+                height = width / ar;
+            } else {
+                width = height * ar;
+            }
+        }
+
+        // resizing keeps the aspect ratio if there is one
+        if let Some((min_width, min_height)) = self.min_size {
+            (width, height) = match self.aspect_ratio {
+                Some(_) => {
+                    let factor = (min_width / width).max(min_height / height).max(1f32);
+                    (width * factor, height * factor)
+                }
+                None => (width.max(min_width), height.max(min_height)),
+            };
+        }
+        if let Some((max_width, max_height)) = self.max_size {
+            (width, height) = match self.aspect_ratio {
+                Some(_) => {
+                    let factor = (max_width / width).min(max_height / height).min(1f32);
+                    (width * factor, height * factor)
+                }
+                None => (width.min(max_width), height.min(max_height)),
+            };
+        }
+
+        // keeps the pixel coordinates below from saturating
+        let max_size = i32::MAX as f32;
+        if !(width <= max_size && height <= max_size) {
+            return None;
+        }
+
+        // round outwards, so the frame never cuts into the bounds
+        let centre_x = bounds.x() + bounds.width() / 2f32;
+        let centre_y = bounds.y() + bounds.height() / 2f32;
+        let left = (centre_x - width / 2f32).floor() as i64;
+        let top = (centre_y - height / 2f32).floor() as i64;
+        let right = (centre_x + width / 2f32).ceil() as i64;
+        let bottom = (centre_y + height / 2f32).ceil() as i64;
+
+        let (left, right) = self.fit_axis(left, right, MAP_WIDTH);
+        let (top, bottom) = self.fit_axis(top, bottom, MAP_HEIGHT);
+
+        let view_left = left.max(0);
+        let view_top = top.max(0);
+        Some(ViewFrame {
+            view_port: image::math::Rect {
+                x: view_left as u32,
+                y: view_top as u32,
+                width: (right.min(MAP_WIDTH as i64) - view_left).max(1) as u32,
+                height: (bottom.min(MAP_HEIGHT as i64) - view_top).max(1) as u32,
+            },
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+            offset: ((view_left - left) as u32, (view_top - top) as u32),
+        })
+    }
+
+    /// Applies the edge policy to one axis of the frame.
+    fn fit_axis(&self, start: i64, end: i64, map_size: u32) -> (i64, i64) {
+        let map_size = map_size as i64;
+        match self.edge_policy {
+            EdgePolicy::Clamp => (start.clamp(0, map_size - 1), end.clamp(1, map_size)),
+            EdgePolicy::Shift if end - start >= map_size => (0, map_size),
+            EdgePolicy::Shift if start < 0 => (0, end - start),
+            EdgePolicy::Shift if end > map_size => (map_size - (end - start), map_size),
+            EdgePolicy::Shift | EdgePolicy::Pad => (start, end),
+        }
+    }
+}
+
+impl ViewFrame {
+    /// Whether the frame extends past the map.
+    pub fn is_padded(&self) -> bool {
+        (self.width, self.height) != (self.view_port.width, self.view_port.height)
+    }
+
+    /// Places an image of the view port rendered at `scale` into an image of the whole frame,
+    /// filling the part outside of the map with `colour`. At [`RenderScale::X4`] the padding is
+    /// rounded up to whole image pixels, so that the image is not shifted against the frame.
+    pub fn pad(&self, image: &RgbaImage, scale: RenderScale, colour: Rgba<u8>) -> RgbaImage {
+        let factor = scale.factor();
+        let left = self.offset.0.div_ceil(factor);
+        let top = self.offset.1.div_ceil(factor);
+        let right = (self.width - self.offset.0 - self.view_port.width).div_ceil(factor);
+        let bottom = (self.height - self.offset.1 - self.view_port.height).div_ceil(factor);
+
+        let mut frame = RgbaImage::from_pixel(
+            left + image.width() + right,
+            top + image.height() + bottom,
+            colour,
+        );
+        overlay(&mut frame, image, left as i64, top as i64);
+        frame
+    }
+}
+
+/// Frames the territories of the embedded dataset, see [`TerritoryDataset::frame_territories`].
+pub fn frame_territories(ids: &[TerritoryId], framing: &Framing) -> Option<ViewFrame> {
+    TerritoryDataset::embedded().frame_territories(ids, framing)
+}
+
+impl TerritoryDataset {
+    /// Frames the union of the territories' bounds. Territories which are not part of the
+    /// dataset are ignored, `None` is returned if none are left or the framing is invalid, see
+    /// [`Framing::frame`].
+    pub fn frame_territories(&self, ids: &[TerritoryId], framing: &Framing) -> Option<ViewFrame> {
+        framing.frame(self.bounds_of_territories(ids)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::territory;
+
+    fn bounds(id: TerritoryId) -> Rect {
+        crate::path_for_territory(id).unwrap().bounds()
+    }
+
+    /// Whether the frame contains the bounds, in map pixels.
+    fn contains(frame: &ViewFrame, bounds: Rect) -> bool {
+        let view_port = frame.view_port;
+        bounds.left() >= view_port.x as f32
+            && bounds.top() >= view_port.y as f32
+            && bounds.right() <= (view_port.x + view_port.width) as f32
+            && bounds.bottom() <= (view_port.y + view_port.height) as f32
+    }
+
+    fn in_map(frame: &ViewFrame) -> bool {
+        frame.view_port.x + frame.view_port.width <= MAP_WIDTH
+            && frame.view_port.y + frame.view_port.height <= MAP_HEIGHT
+    }
+
+    /// A territory at the left edge of the map, whose outline reaches just past it.
+    fn edge_territory() -> TerritoryId {
+        let dataset = TerritoryDataset::embedded();
+        dataset
+            .ids()
+            .min_by(|a, b| bounds(*a).left().total_cmp(&bounds(*b).left()))
+            .unwrap()
+    }
+
+    fn tiny_territory() -> TerritoryId {
+        let area = |id| {
+            let bounds = bounds(id);
+            bounds.width() * bounds.height()
+        };
+        let dataset = TerritoryDataset::embedded();
+        dataset
+            .ids()
+            .min_by(|a, b| area(*a).total_cmp(&area(*b)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_padding() {
+        let xod = bounds(territory!("XOD"));
+        let framing = Framing {
+            padding: Padding::Pixels(20f32),
+            aspect_ratio: None,
+            min_size: None,
+            ..Default::default()
+        };
+        let frame = framing.frame(xod).unwrap();
+        assert!(contains(&frame, xod));
+        assert!(!frame.is_padded());
+        // rounding adds at most one pixel on either side
+        let width = frame.view_port.width as f32 - (xod.width() + 40f32);
+        assert!((0f32..=2f32).contains(&width), "{width}");
+
+        let framing = Framing {
+            padding: Padding::Percent(50f32),
+            ..framing
+        };
+        let frame = framing.frame(xod).unwrap();
+        let height = frame.view_port.height as f32 - 2f32 * xod.height();
+        assert!((0f32..=2f32).contains(&height), "{height}");
+
+        let frame = Framing::default().frame(xod).unwrap();
+        let ar = frame.view_port.width as f32 / frame.view_port.height as f32;
+        assert!((ar - 4f32 / 3f32).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_invalid_framing() {
+        let xod = bounds(territory!("XOD"));
+        let framing = |aspect_ratio, padding| Framing {
+            padding,
+            aspect_ratio,
+            ..Default::default()
+        };
+
+        for aspect_ratio in [0f32, -1f32, f32::NAN, f32::INFINITY] {
+            assert_eq!(
+                framing(Some(aspect_ratio), Padding::Pixels(0f32)).frame(xod),
+                None
+            );
+        }
+        for padding in [f32::NAN, f32::INFINITY, f32::MAX, -1e6f32] {
+            assert_eq!(framing(None, Padding::Pixels(padding)).frame(xod), None);
+            assert_eq!(framing(None, Padding::Percent(padding)).frame(xod), None);
+        }
+        let sized = |size| Framing {
+            min_size: Some(size),
+            ..Default::default()
+        };
+        assert_eq!(sized((0f32, 240f32)).frame(xod), None);
+        assert_eq!(sized((320f32, f32::NAN)).frame(xod), None);
+        let capped = Framing {
+            max_size: Some((-1f32, 100f32)),
+            ..Default::default()
+        };
+        assert_eq!(capped.frame(xod), None);
+
+        // padding may crop the territories as long as something is left
+        let cropped = Framing {
+            min_size: None,
+            ..framing(None, Padding::Percent(-25f32))
+        };
+        let cropped = cropped.frame(xod).unwrap();
+        assert!(cropped.width as f32 <= xod.width() / 2f32 + 2f32);
+    }
+
+    #[test]
+    fn test_tiny_territory() {
+        let id = tiny_territory();
+        let framing = Framing::default();
+        let frame = frame_territories(&[id], &framing).unwrap();
+        assert!(contains(&frame, bounds(id)));
+        assert!(frame.width >= 320 && frame.height >= 240);
+
+        // the territory stays in the centre
+        let centre_x = frame.view_port.x as f32 + frame.view_port.width as f32 / 2f32;
+        let bounds = bounds(id);
+        assert!((centre_x - (bounds.x() + bounds.width() / 2f32)).abs() <= 1f32);
+
+        let unlimited = Framing {
+            min_size: None,
+            ..framing
+        };
+        let frame = frame_territories(&[id], &unlimited).unwrap();
+        assert!(frame.width < 320);
+
+        let capped = Framing {
+            max_size: Some((100f32, 100f32)),
+            ..Framing::default()
+        };
+        let frame = frame_territories(&[territory!("XOD"), territory!("GVE")], &capped).unwrap();
+        assert!(frame.width <= 101 && frame.height <= 101);
+    }
+
+    #[test]
+    fn test_edge_policies() {
+        let id = edge_territory();
+        let bounds = bounds(id);
+        let framing = |edge_policy| Framing {
+            padding: Padding::Pixels(50f32),
+            edge_policy,
+            ..Default::default()
+        };
+
+        let clamp = frame_territories(&[id], &framing(EdgePolicy::Clamp)).unwrap();
+        let shift = frame_territories(&[id], &framing(EdgePolicy::Shift)).unwrap();
+        let pad = frame_territories(&[id], &framing(EdgePolicy::Pad)).unwrap();
+        for frame in [&clamp, &shift, &pad] {
+            assert!(in_map(frame));
+            assert_eq!(frame.view_port.x, 0);
+        }
+
+        // clamping cuts off the part past the edge
+        assert!(!clamp.is_padded());
+        assert!(clamp.view_port.width < pad.width);
+        assert_eq!(clamp.view_port.height, pad.height);
+
+        // shifting keeps the size, but moves the territory off-centre
+        assert!(!shift.is_padded());
+        assert_eq!(shift.view_port.width, pad.width);
+        assert!(shift.view_port.width as f32 / 2f32 > bounds.x() + bounds.width() / 2f32 + 2f32);
+
+        // padding keeps the territory centred
+        assert!(pad.is_padded());
+        assert!(pad.offset.0 > 0);
+        assert_eq!(pad.offset.1, 0);
+        let centre = pad.width as f32 / 2f32 - pad.offset.0 as f32;
+        assert!((centre - (bounds.x() + bounds.width() / 2f32)).abs() <= 1f32);
+
+        // frames larger than the map are never shifted off of it
+        let all: Vec<_> = TerritoryDataset::embedded().ids().collect();
+        let frame = frame_territories(&all, &framing(EdgePolicy::Shift)).unwrap();
+        assert_eq!(frame.view_port, crate::MAP_BBOX);
+    }
+
+    #[cfg(feature = "embedded-tiles")]
+    #[test]
+    fn test_pad() {
+        let framing = Framing {
+            edge_policy: EdgePolicy::Pad,
+            ..Default::default()
+        };
+        let frame = frame_territories(&[edge_territory()], &framing).unwrap();
+        let image = crate::load_map_segment(
+            frame.view_port.x,
+            frame.view_port.y,
+            frame.view_port.width,
+            frame.view_port.height,
+        );
+        let image = image::DynamicImage::ImageLuma8(image).to_rgba8();
+
+        let red = Rgba([255, 0, 0, 255]);
+        let padded = frame.pad(&image, RenderScale::X1, red);
+        assert_eq!(padded.dimensions(), (frame.width, frame.height));
+        assert_eq!(*padded.get_pixel(0, 0), red);
+        assert_eq!(
+            padded.get_pixel(frame.offset.0, frame.offset.1),
+            image.get_pixel(0, 0)
+        );
+
+        let x4 = image::imageops::resize(
+            &image,
+            frame.view_port.width / 4,
+            frame.view_port.height / 4,
+            image::imageops::FilterType::Nearest,
+        );
+        let padded = frame.pad(&x4, RenderScale::X4, red);
+        let left = frame.offset.0.div_ceil(4);
+        assert!(padded.width().abs_diff(frame.width / 4) <= 2);
+        assert!(padded.height().abs_diff(frame.height / 4) <= 2);
+        assert_eq!(*padded.get_pixel(left - 1, 0), red);
+        assert_eq!(padded.get_pixel(left, 0), x4.get_pixel(0, 0));
+    }
+}
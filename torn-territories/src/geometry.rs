@@ -0,0 +1,185 @@
+use tiny_skia_path::{Path, PathBuilder, Rect};
+
+use crate::{PathSegment, TerritoryDataset, TerritoryId, TerritoryInfo, MAP_HEIGHT, MAP_WIDTH};
+
+impl TerritoryInfo {
+    pub fn path(&self) -> Option<Path> {
+        path_for_shape(&self.shape)
+    }
+}
+
+pub(crate) fn path_for_shape(shape: &[PathSegment]) -> Option<Path> {
+    let mut builder = PathBuilder::new();
+
+    for inst in shape {
+        match inst {
+            PathSegment::MoveTo { x, y } => {
+                builder.move_to(*x as f32, *y as f32);
+            }
+            PathSegment::LineTo { x, y } => {
+                builder.line_to(*x as f32, *y as f32);
+            }
+            PathSegment::Quadratic { x1, y1, x, y } => {
+                builder.quad_to(*x1 as f32, *y1 as f32, *x as f32, *y as f32);
+            }
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                builder.cubic_to(
+                    *x1 as f32, *y1 as f32, *x2 as f32, *y2 as f32, *x as f32, *y as f32,
+                );
+            }
+            PathSegment::ClosePath => {
+                builder.close();
+            }
+        }
+    }
+
+    builder.finish()
+}
+
+pub fn path_for_territory(id: TerritoryId) -> Option<Path> {
+    TerritoryDataset::embedded().path_for_territory(id)
+}
+
+/// # Panics
+///
+/// If `factor` or `ar` are not positive, or so extreme that the box is no longer finite.
+pub fn bbox_for_path(path: &Path, factor: f32, ar: f32) -> Rect {
+    checked_bbox_for_path(path, factor, ar).unwrap()
+}
+
+/// Like [`bbox_for_path`], but `None` if `factor` or `ar` are not positive or the box is not
+/// finite.
+pub(crate) fn checked_bbox_for_path(path: &Path, factor: f32, ar: f32) -> Option<Rect> {
+    if !(factor > 0f32 && ar > 0f32) {
+        return None;
+    }
+
+    let bounds = path.bounds();
+
+    let bounds_ar = bounds.width() / bounds.height();
+    if bounds_ar > ar {
+        let width = bounds.width() / factor;
+        let height = width / ar;
+
+        let x = bounds.x() - (width * (1f32 - factor) / 2f32);
+        let y = bounds.y() - (height - bounds.height()) / 2f32;
+
+        Rect::from_xywh(x, y, width, height)
+    } else {
+        let height = bounds.height() / factor;
+        let width = height * ar;
+
+        let y = bounds.y() - (height * (1f32 - factor) / 2f32);
+        let x = bounds.x() - (width - bounds.width()) / 2f32;
+
+        Rect::from_xywh(x, y, width, height)
+    }
+}
+
+/// Frames several territories like [`bbox_for_path`] and keeps the result inside of the map, see
+/// [`TerritoryDataset::bbox_for_territories`].
+pub fn bbox_for_territories(ids: &[TerritoryId], factor: f32, ar: f32) -> Option<Rect> {
+    TerritoryDataset::embedded().bbox_for_territories(ids, factor, ar)
+}
+
+impl TerritoryDataset {
+    pub fn path_for_territory(&self, id: TerritoryId) -> Option<Path> {
+        self.get(id)?.path()
+    }
+
+    /// Frames the union of the territories' bounds like [`bbox_for_path`]. The box is moved back
+    /// onto the map if it reaches past an edge, and only shrunk if it is larger than the map.
+    /// Territories which are not part of the dataset are ignored, `None` is returned if none are
+    /// left or if `factor` and `ar` do not result in a finite box.
+    pub fn bbox_for_territories(&self, ids: &[TerritoryId], factor: f32, ar: f32) -> Option<Rect> {
+        let bounds = self.bounds_of_territories(ids)?;
+        let bbox = checked_bbox_for_path(&PathBuilder::from_rect(bounds), factor, ar)?;
+        let width = bbox.width().min(MAP_WIDTH as f32);
+        let height = bbox.height().min(MAP_HEIGHT as f32);
+        let x = bbox.x().clamp(0f32, MAP_WIDTH as f32 - width);
+        let y = bbox.y().clamp(0f32, MAP_HEIGHT as f32 - height);
+
+        Rect::from_xywh(x, y, width, height)
+    }
+
+    /// The union of the territories' bounds, ignoring territories which are not part of the
+    /// dataset.
+    pub fn bounds_of_territories(&self, ids: &[TerritoryId]) -> Option<Rect> {
+        ids.iter()
+            .filter_map(|id| Some(self.path_for_territory(*id)?.bounds()))
+            .reduce(|a, b| {
+                Rect::from_ltrb(
+                    a.left().min(b.left()),
+                    a.top().min(b.top()),
+                    a.right().max(b.right()),
+                    a.bottom().max(b.bottom()),
+                )
+                .unwrap()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::territory;
+
+    fn contains(outer: Rect, inner: Rect) -> bool {
+        outer.left() <= inner.left()
+            && outer.top() <= inner.top()
+            && outer.right() >= inner.right()
+            && outer.bottom() >= inner.bottom()
+    }
+
+    #[test]
+    fn test_bbox_for_territories() {
+        let ids = [territory!("XOD"), territory!("GVE")];
+        let bbox = bbox_for_territories(&ids, 0.8, 4f32 / 3f32).unwrap();
+        for id in ids {
+            let bounds = path_for_territory(id).unwrap().bounds();
+            assert!(contains(bbox, bounds));
+        }
+        assert!((bbox.width() / bbox.height() - 4f32 / 3f32).abs() < 0.01);
+
+        // a single territory is framed like `bbox_for_path`
+        let path = path_for_territory(territory!("XOD")).unwrap();
+        assert_eq!(
+            bbox_for_territories(&ids[..1], 0.5, 1f32),
+            Some(bbox_for_path(&path, 0.5, 1f32))
+        );
+
+        // territories at the edge are framed without leaving the map
+        let dataset = TerritoryDataset::embedded();
+        let leftmost = dataset
+            .ids()
+            .min_by(|a, b| {
+                let a = dataset.path_for_territory(*a).unwrap().bounds().left();
+                let b = dataset.path_for_territory(*b).unwrap().bounds().left();
+                a.total_cmp(&b)
+            })
+            .unwrap();
+        let bbox = bbox_for_territories(&[leftmost], 0.2, 1f32).unwrap();
+        assert_eq!(bbox.x(), 0f32);
+        // the outline reaches slightly past the edge
+        let bounds = dataset.path_for_territory(leftmost).unwrap().bounds();
+        assert!(contains(
+            bbox,
+            Rect::from_ltrb(0f32, bounds.top(), bounds.right(), bounds.bottom()).unwrap()
+        ));
+
+        let all: Vec<_> = dataset.ids().collect();
+        let bbox = bbox_for_territories(&all, 0.5, 1f32).unwrap();
+        assert_eq!((bbox.x(), bbox.y()), (0f32, 0f32));
+        assert_eq!(bbox.height(), MAP_HEIGHT as f32);
+        assert!(bbox.width() <= MAP_WIDTH as f32);
+
+        assert_eq!(bbox_for_territories(&[], 1f32, 1f32), None);
+    }
+}
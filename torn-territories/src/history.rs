@@ -0,0 +1,463 @@
+use crate::{TerritoryId, TerritoryMap, TerritorySet};
+
+/// The ID of whoever holds a territory, usually a faction ID.
+pub type OwnerId = u32;
+
+/// Seconds since the Unix epoch.
+pub type Timestamp = i64;
+
+/// A territory changed hands at `at`. `None` means the territory became unclaimed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnershipEvent {
+    pub at: Timestamp,
+    pub owner: Option<OwnerId>,
+}
+
+/// One entry of [`OwnershipTimeline::changes_between`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OwnershipChange {
+    pub territory: TerritoryId,
+    pub at: Timestamp,
+    pub before: Option<OwnerId>,
+    pub after: Option<OwnerId>,
+}
+
+/// How long a territory has been held by its owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tenure {
+    pub owner: OwnerId,
+    /// When the owner took the territory. This is the time of the first snapshot in which the
+    /// owner held it, so the actual capture may have happened up to one snapshot interval earlier.
+    pub since: Timestamp,
+}
+
+/// How the ownership changed between two snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnershipDiff {
+    /// Unclaimed territories that were claimed, with their new owner.
+    pub gained: TerritoryMap<OwnerId>,
+    /// Territories that became unclaimed, with their previous owner.
+    pub lost: TerritoryMap<OwnerId>,
+    /// Territories that changed hands, as `(before, after)`.
+    pub transferred: TerritoryMap<(OwnerId, OwnerId)>,
+    /// Territories that kept their owner.
+    pub unchanged: TerritoryMap<OwnerId>,
+}
+
+impl OwnershipDiff {
+    pub fn new(before: &TerritoryMap<OwnerId>, after: &TerritoryMap<OwnerId>) -> Self {
+        let mut diff = Self::default();
+        for id in before.keys().union(&after.keys()).iter() {
+            match (before.get(id).copied(), after.get(id).copied()) {
+                (None, Some(owner)) => {
+                    diff.gained.insert(id, owner);
+                }
+                (Some(owner), None) => {
+                    diff.lost.insert(id, owner);
+                }
+                (Some(before), Some(after)) if before != after => {
+                    diff.transferred.insert(id, (before, after));
+                }
+                (Some(owner), Some(_)) => {
+                    diff.unchanged.insert(id, owner);
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gained.is_empty() && self.lost.is_empty() && self.transferred.is_empty()
+    }
+
+    /// All territories that were gained, lost or transferred.
+    pub fn changed(&self) -> TerritorySet {
+        self.gained.keys() | self.lost.keys() | self.transferred.keys()
+    }
+}
+
+/// The ownership of all territories over time, stored as the changes of every territory.
+///
+/// Territories are unclaimed before their first event. Only actual changes are stored, so
+/// recording the same ownership every hour does not grow the timeline.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        from = "TerritoryMap<Vec<OwnershipEvent>>",
+        into = "TerritoryMap<Vec<OwnershipEvent>>"
+    )
+)]
+pub struct OwnershipTimeline {
+    /// Sorted by time, without consecutive events of the same owner.
+    events: TerritoryMap<Vec<OwnershipEvent>>,
+}
+
+impl OwnershipTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the ownership of all territories at `at`. Territories which are not part of
+    /// `owners` are unclaimed. Snapshots should be recorded in chronological order, as only
+    /// changes are kept and an earlier snapshot overrides the ownership until the next change.
+    pub fn record(&mut self, at: Timestamp, owners: &TerritoryMap<OwnerId>) {
+        for id in self.events.keys().union(&owners.keys()).iter() {
+            self.set_owner(id, at, owners.get(id).copied());
+        }
+    }
+
+    /// Records the owner of a single territory from `at` until the next recorded change.
+    pub fn set_owner(&mut self, id: TerritoryId, at: Timestamp, owner: Option<OwnerId>) {
+        if self.events.get(id).is_none() {
+            if owner.is_none() {
+                return;
+            }
+            self.events.insert(id, Vec::new());
+        }
+        let events = self.events.get_mut(id).unwrap();
+
+        let index = match events.binary_search_by_key(&at, |e| e.at) {
+            Ok(index) => {
+                events[index].owner = owner;
+                index
+            }
+            Err(index) => {
+                let previous = index.checked_sub(1).map(|i| events[i].owner);
+                if previous.unwrap_or(None) == owner {
+                    return;
+                }
+                events.insert(index, OwnershipEvent { at, owner });
+                index
+            }
+        };
+
+        // the event may have made its neighbours redundant
+        if events
+            .get(index + 1)
+            .is_some_and(|next| next.owner == owner)
+        {
+            events.remove(index + 1);
+        }
+        let previous = index.checked_sub(1).map(|i| events[i].owner);
+        if previous.unwrap_or(None) == owner {
+            events.remove(index);
+        }
+
+        if events.is_empty() {
+            self.events.remove(id);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Everyone who owned a territory at some point, sorted by ID.
+    pub fn owner_ids(&self) -> Vec<OwnerId> {
+        let mut owners: Vec<_> = self
+            .events
+            .values()
+            .flatten()
+            .filter_map(|e| e.owner)
+            .collect();
+        owners.sort();
+        owners.dedup();
+        owners
+    }
+
+    /// All changes of the territory, sorted by time.
+    pub fn history(&self, id: TerritoryId) -> &[OwnershipEvent] {
+        self.events.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The times of the first and the last change.
+    pub fn span(&self) -> Option<(Timestamp, Timestamp)> {
+        let first = self
+            .events
+            .values()
+            .filter_map(|e| e.first())
+            .map(|e| e.at)
+            .min()?;
+        let last = self
+            .events
+            .values()
+            .filter_map(|e| e.last())
+            .map(|e| e.at)
+            .max()?;
+        Some((first, last))
+    }
+
+    fn event_at(&self, id: TerritoryId, at: Timestamp) -> Option<(usize, &OwnershipEvent)> {
+        let events = self.events.get(id)?;
+        let index = events.partition_point(|e| e.at <= at).checked_sub(1)?;
+        Some((index, &events[index]))
+    }
+
+    pub fn owner_at(&self, id: TerritoryId, at: Timestamp) -> Option<OwnerId> {
+        self.event_at(id, at)?.1.owner
+    }
+
+    /// The ownership of all territories at `at`.
+    pub fn snapshot_at(&self, at: Timestamp) -> TerritoryMap<OwnerId> {
+        self.events
+            .keys()
+            .iter()
+            .filter_map(|id| Some((id, self.owner_at(id, at)?)))
+            .collect()
+    }
+
+    /// Who held the territory at `at` and since when.
+    pub fn tenure(&self, id: TerritoryId, at: Timestamp) -> Option<Tenure> {
+        let (_, event) = self.event_at(id, at)?;
+        Some(Tenure {
+            owner: event.owner?,
+            since: event.at,
+        })
+    }
+
+    /// Since when the owner held each of its territories at `at`.
+    pub fn tenures_of(&self, owner: OwnerId, at: Timestamp) -> TerritoryMap<Timestamp> {
+        self.events
+            .keys()
+            .iter()
+            .filter_map(|id| {
+                let tenure = self.tenure(id, at)?;
+                (tenure.owner == owner).then_some((id, tenure.since))
+            })
+            .collect()
+    }
+
+    /// Every change after `from` up to and including `to`, sorted by time and territory.
+    pub fn changes_between(&self, from: Timestamp, to: Timestamp) -> Vec<OwnershipChange> {
+        let mut changes = Vec::new();
+        for (id, events) in self.events.iter() {
+            let start = events.partition_point(|e| e.at <= from);
+            let end = events.partition_point(|e| e.at <= to);
+            for index in start..end.max(start) {
+                changes.push(OwnershipChange {
+                    territory: id,
+                    at: events[index].at,
+                    before: index.checked_sub(1).and_then(|i| events[i].owner),
+                    after: events[index].owner,
+                });
+            }
+        }
+
+        changes.sort_by_key(|c| (c.at, c.territory));
+        changes
+    }
+
+    /// The net change of every territory whose owner at `to` differs from the one at `from`, as
+    /// `(before, after)`. Territories that changed hands and were taken back in between are not
+    /// part of the result.
+    pub fn diff(
+        &self,
+        from: Timestamp,
+        to: Timestamp,
+    ) -> TerritoryMap<(Option<OwnerId>, Option<OwnerId>)> {
+        self.events
+            .keys()
+            .iter()
+            .filter_map(|id| {
+                let before = self.owner_at(id, from);
+                let after = self.owner_at(id, to);
+                (before != after).then_some((id, (before, after)))
+            })
+            .collect()
+    }
+}
+
+impl From<TerritoryMap<Vec<OwnershipEvent>>> for OwnershipTimeline {
+    /// The events do not need to be sorted, redundant events are dropped.
+    fn from(value: TerritoryMap<Vec<OwnershipEvent>>) -> Self {
+        let mut timeline = Self::new();
+        for (id, mut events) in value {
+            events.sort_by_key(|e| e.at);
+            for event in events {
+                timeline.set_owner(id, event.at, event.owner);
+            }
+        }
+        timeline
+    }
+}
+
+impl From<OwnershipTimeline> for TerritoryMap<Vec<OwnershipEvent>> {
+    fn from(value: OwnershipTimeline) -> Self {
+        value.events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::territory;
+
+    const HOUR: Timestamp = 3600;
+
+    fn owners(entries: &[(TerritoryId, OwnerId)]) -> TerritoryMap<OwnerId> {
+        entries.iter().copied().collect()
+    }
+
+    fn timeline() -> OwnershipTimeline {
+        let (xod, gve) = (territory!("XOD"), territory!("GVE"));
+        let mut timeline = OwnershipTimeline::new();
+        timeline.record(0, &owners(&[(xod, 1), (gve, 2)]));
+        timeline.record(HOUR, &owners(&[(xod, 1), (gve, 2)]));
+        timeline.record(2 * HOUR, &owners(&[(xod, 2), (gve, 2)]));
+        timeline.record(3 * HOUR, &owners(&[(xod, 2)]));
+        timeline.record(4 * HOUR, &owners(&[(xod, 1)]));
+        timeline
+    }
+
+    #[test]
+    fn test_point_in_time() {
+        let timeline = timeline();
+        let (xod, gve) = (territory!("XOD"), territory!("GVE"));
+
+        // repeated snapshots are not stored
+        assert_eq!(timeline.history(xod).len(), 3);
+        assert_eq!(timeline.history(gve).len(), 2);
+        assert!(timeline.history(territory!("VOD")).is_empty());
+        assert_eq!(timeline.span(), Some((0, 4 * HOUR)));
+        assert_eq!(timeline.owner_ids(), [1, 2]);
+
+        assert_eq!(timeline.owner_at(xod, -1), None);
+        assert_eq!(timeline.owner_at(xod, HOUR + 1), Some(1));
+        assert_eq!(timeline.owner_at(xod, 2 * HOUR), Some(2));
+        assert_eq!(timeline.owner_at(gve, 3 * HOUR), None);
+
+        let snapshot = timeline.snapshot_at(3 * HOUR);
+        assert_eq!(snapshot, owners(&[(xod, 2)]));
+        assert!(timeline.snapshot_at(-HOUR).is_empty());
+    }
+
+    #[test]
+    fn test_tenure() {
+        let timeline = timeline();
+        let (xod, gve) = (territory!("XOD"), territory!("GVE"));
+
+        assert_eq!(
+            timeline.tenure(gve, 2 * HOUR),
+            Some(Tenure { owner: 2, since: 0 })
+        );
+        assert_eq!(timeline.tenure(gve, 3 * HOUR), None);
+        assert_eq!(timeline.tenure(xod, 10 * HOUR).unwrap().since, 4 * HOUR);
+
+        let tenures = timeline.tenures_of(2, 2 * HOUR);
+        assert_eq!(
+            tenures,
+            TerritoryMap::from_iter([(xod, 2 * HOUR), (gve, 0)])
+        );
+        assert!(timeline.tenures_of(2, 4 * HOUR).is_empty());
+    }
+
+    #[test]
+    fn test_changes() {
+        let timeline = timeline();
+        let (xod, gve) = (territory!("XOD"), territory!("GVE"));
+
+        let changes = timeline.changes_between(HOUR, 4 * HOUR);
+        assert_eq!(
+            changes,
+            [
+                OwnershipChange {
+                    territory: xod,
+                    at: 2 * HOUR,
+                    before: Some(1),
+                    after: Some(2)
+                },
+                OwnershipChange {
+                    territory: gve,
+                    at: 3 * HOUR,
+                    before: Some(2),
+                    after: None
+                },
+                OwnershipChange {
+                    territory: xod,
+                    at: 4 * HOUR,
+                    before: Some(2),
+                    after: Some(1)
+                },
+            ]
+        );
+        assert!(timeline.changes_between(4 * HOUR, HOUR).is_empty());
+
+        // XOD was taken back, so only GVE changed in total
+        let diff = timeline.diff(HOUR, 4 * HOUR);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[gve], (Some(2), None));
+    }
+
+    #[test]
+    fn test_ownership_diff() {
+        let timeline = timeline();
+        let (xod, gve, vod) = (territory!("XOD"), territory!("GVE"), territory!("VOD"));
+
+        let before = timeline.snapshot_at(2 * HOUR);
+        let mut after = timeline.snapshot_at(4 * HOUR);
+        after.insert(vod, 3);
+        let diff = OwnershipDiff::new(&before, &after);
+        assert_eq!(diff.gained, TerritoryMap::from_iter([(vod, 3)]));
+        assert_eq!(diff.lost, TerritoryMap::from_iter([(gve, 2)]));
+        assert_eq!(diff.transferred, TerritoryMap::from_iter([(xod, (2, 1))]));
+        assert!(diff.unchanged.is_empty());
+        assert_eq!(diff.changed().len(), 3);
+
+        let diff = OwnershipDiff::new(&before, &before);
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged.len(), 2);
+    }
+
+    #[test]
+    fn test_set_owner() {
+        let mut timeline = timeline();
+        let xod = territory!("XOD");
+
+        // overwriting a change can make the surrounding changes redundant
+        timeline.set_owner(xod, 4 * HOUR, Some(2));
+        assert_eq!(timeline.history(xod).len(), 2);
+        timeline.set_owner(xod, 3 * HOUR, Some(1));
+        assert_eq!(timeline.history(xod).len(), 3);
+        timeline.set_owner(xod, 2 * HOUR, Some(1));
+        assert_eq!(
+            timeline.history(xod),
+            [OwnershipEvent {
+                at: 0,
+                owner: Some(1)
+            }]
+        );
+
+        timeline.set_owner(xod, 0, None);
+        assert!(timeline.history(xod).is_empty());
+        timeline.set_owner(territory!("GVE"), 0, None);
+        assert!(timeline.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let timeline = timeline();
+        let json = serde_json::to_value(&timeline).unwrap();
+        assert_eq!(
+            json["XOD"][1],
+            serde_json::json!({ "at": 2 * HOUR, "owner": 2 })
+        );
+        assert_eq!(json["GVE"][1]["owner"], serde_json::Value::Null);
+        assert_eq!(
+            serde_json::from_value::<OwnershipTimeline>(json).unwrap(),
+            timeline
+        );
+
+        // unsorted and redundant events are normalised
+        let parsed: OwnershipTimeline = serde_json::from_str(
+            r#"{ "XOD": [{ "at": 10, "owner": 1 }, { "at": 0, "owner": 1 }, { "at": 5, "owner": null }] }"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.history(territory!("XOD")).len(), 3);
+        assert_eq!(parsed.owner_at(territory!("XOD"), 7), None);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{TerritoryId, TerritoryIdError};
+
+/// A [`TerritoryId`] which is (de)serialised and stored as its dense [index](TerritoryId::index)
+/// instead of its three letters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TerritoryIndex(pub TerritoryId);
+
+impl From<TerritoryId> for TerritoryIndex {
+    fn from(value: TerritoryId) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TerritoryIndex> for TerritoryId {
+    fn from(value: TerritoryIndex) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<u16> for TerritoryIndex {
+    type Error = TerritoryIdError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        TerritoryId::from_index(value)
+            .map(Self)
+            .ok_or(TerritoryIdError::InvalidIndex(value))
+    }
+}
+
+impl From<TerritoryIndex> for u16 {
+    fn from(value: TerritoryIndex) -> Self {
+        value.0.index()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TerritoryIndex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let index = <u16 as serde::Deserialize>::deserialize(deserializer)?;
+        index.try_into().map_err(|_why| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Unsigned(index.into()),
+                &"A valid territory index",
+            )
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TerritoryIndex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u16(self.0.index())
+    }
+}
+
+/// (De)serialises a [`TerritoryId`] as its dense index. Meant to be used with
+/// `#[serde(with = "torn_territories::serde_index")]`.
+#[cfg(feature = "serde")]
+pub mod serde_index {
+    use super::TerritoryIndex;
+    use crate::TerritoryId;
+
+    pub fn serialize<S>(id: &TerritoryId, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serde::Serialize::serialize(&TerritoryIndex(*id), serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<TerritoryId, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        <TerritoryIndex as serde::Deserialize>::deserialize(deserializer).map(|i| i.0)
+    }
+}
+
+/// Stored as `SMALLINT`, since not all databases support unsigned integers.
+#[cfg(feature = "sqlx")]
+impl<DB> sqlx::Type<DB> for TerritoryIndex
+where
+    DB: sqlx::Database,
+    i16: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <i16 as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &<DB as sqlx::Database>::TypeInfo) -> bool {
+        <i16 as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<DB> sqlx::Decode<'_, DB> for TerritoryIndex
+where
+    DB: sqlx::Database,
+    i16: for<'r> sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::database::HasValueRef<'_>>::ValueRef,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        let value = <i16 as sqlx::Decode<'_, DB>>::decode(value)?;
+        Ok(u16::try_from(value)?.try_into()?)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<DB> sqlx::Encode<'_, DB> for TerritoryIndex
+where
+    DB: sqlx::Database,
+    i16: for<'q> sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as sqlx::database::HasArguments<'_>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        // indices are guaranteed to fit, see `build.rs`
+        <i16 as sqlx::Encode<'_, DB>>::encode(self.0.index() as i16, buf)
+    }
+}
+
+#[cfg(feature = "sqlx-postgres")]
+impl sqlx::postgres::PgHasArrayType for TerritoryIndex {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        <i16 as sqlx::postgres::PgHasArrayType>::array_type_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_roundtrip() {
+        for id in crate::TerritoryDataset::embedded().ids() {
+            assert!((id.index() as usize) < TerritoryId::INDEX_LEN);
+            assert_eq!(TerritoryId::from_index(id.index()), Some(id));
+        }
+
+        assert_eq!(TerritoryId::from_index(TerritoryId::INDEX_LEN as u16), None);
+    }
+
+    #[test]
+    fn test_index_stable() {
+        // indices must never change, this catches accidental edits of `territory_index.txt`
+        assert_eq!(crate::territory!("AAB").index(), 0);
+        assert_eq!(crate::territory!("GVE").index(), 1091);
+        assert_eq!(crate::territory!("XOD").index(), 3767);
+    }
+
+    #[test]
+    fn test_index_matches_file() {
+        let index = include_str!("../static/territory_index.txt");
+        for (line, id) in index.lines().enumerate() {
+            match TerritoryId::from_index(line as u16) {
+                Some(found) => assert_eq!(found.as_str(), id, "line {}", line + 1),
+                None => assert!(id.parse::<TerritoryId>().is_err(), "line {}", line + 1),
+            }
+        }
+        assert_eq!(index.lines().count(), TerritoryId::INDEX_LEN);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_index() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Row {
+            #[serde(with = "crate::serde_index")]
+            territory: TerritoryId,
+        }
+
+        let id = crate::territory!("XOD");
+        let value = serde_json::to_value(Row { territory: id }).unwrap();
+        assert_eq!(value, serde_json::json!({ "territory": id.index() }));
+
+        let row: Row = serde_json::from_value(value).unwrap();
+        assert_eq!(row.territory, id);
+
+        assert!(serde_json::from_value::<TerritoryIndex>(serde_json::json!(u16::MAX)).is_err());
+    }
+}
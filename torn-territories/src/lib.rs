@@ -1,45 +1,154 @@
-use std::{collections::HashMap, io::Cursor, ops::Deref, rc::Rc};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use image::{
-    buffer::ConvertBuffer,
-    codecs::tiff::TiffDecoder,
-    imageops::{crop, overlay, replace},
-    ColorType, GenericImageView, GrayImage, ImageDecoder,
+extern crate alloc;
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+#[cfg(feature = "animation")]
+mod animation;
+mod collections;
+mod dataset;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "render")]
+mod framing;
+#[cfg(feature = "geometry")]
+mod geometry;
+#[cfg(feature = "std")]
+mod history;
+mod index;
+#[cfg(feature = "render")]
+mod ownership;
+#[cfg(feature = "render")]
+mod render;
+#[cfg(feature = "serde")]
+mod serde_helpers;
+mod shape;
+#[cfg(feature = "render")]
+mod spec;
+#[cfg(feature = "render")]
+mod text;
+#[cfg(feature = "render")]
+mod tiles;
+#[cfg(feature = "torn-api")]
+pub mod torn_api;
+#[cfg(feature = "json")]
+mod validation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(all(feature = "animation", feature = "embedded-tiles"))]
+pub use animation::render_animation;
+#[cfg(feature = "animation")]
+pub use animation::{format_timestamp, AnimationFormat, AnimationFrame, AnimationOptions};
+pub use collections::{MapIntoIter, SetIter, TerritoryMap, TerritorySet};
+#[cfg(feature = "json")]
+pub use dataset::DatasetError;
+pub use dataset::{Iter, TerritoryDataset};
+#[cfg(feature = "json")]
+pub use diff::diff_json_datasets;
+#[cfg(feature = "std")]
+pub use diff::{diff_datasets, DatasetDiff, TerritoryChange};
+#[cfg(feature = "render")]
+pub use framing::{frame_territories, EdgePolicy, Framing, Padding, ViewFrame};
+#[cfg(feature = "geometry")]
+pub use geometry::{bbox_for_path, bbox_for_territories, path_for_territory};
+#[cfg(feature = "std")]
+pub use history::{
+    OwnerId, OwnershipChange, OwnershipDiff, OwnershipEvent, OwnershipTimeline, Tenure, Timestamp,
+};
+#[cfg(feature = "serde")]
+pub use index::serde_index;
+pub use index::TerritoryIndex;
+#[cfg(feature = "render")]
+pub use ownership::{auto_colour, DiffOptions, OwnerStyle, OwnershipSnapshot};
+#[cfg(feature = "embedded-tiles")]
+pub use ownership::{render_ownership, render_ownership_diff};
+#[cfg(feature = "embedded-tiles")]
+pub use render::render_territories;
+#[cfg(feature = "render")]
+pub use render::{
+    colour_from_hex, colour_to_hex, element_for_territory, fit_view_box, RenderInstruction,
+    RenderScale, MAP_BBOX,
+};
+pub use shape::PathSegment;
+#[cfg(feature = "embedded-tiles")]
+pub use spec::render_spec;
+#[cfg(feature = "render")]
+pub use spec::{OutputFormat, RenderError, RenderLayer, RenderSpec, Viewport};
+#[cfg(feature = "embedded-tiles")]
+pub use tiles::{load_map_segment, load_map_x4, EmbeddedTiles};
+#[cfg(feature = "render")]
+pub use tiles::{
+    load_map_segment_from, load_map_x4_from, tile_names, MemoryTiles, TileError, TileSource,
 };
-use resvg::usvg::{self, NodeExt, Rect};
-use rust_embed::RustEmbed;
-use svgtypes::SimplePathSegment;
+#[cfg(feature = "geometry")]
+pub use tiny_skia_path;
 
 #[derive(Debug, Clone)]
 pub enum TerritoryIdError {
     InvalidLength(usize),
-    DoesNotExist,
+    DoesNotExist {
+        input: String,
+        /// The closest existing ids by edit distance, best match first.
+        suggestions: Vec<TerritoryId>,
+    },
     InvalidEncoding,
+    InvalidIndex(u16),
 }
 
-impl std::fmt::Display for TerritoryIdError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for TerritoryIdError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
             Self::InvalidLength(len) => write!(f, "InvalidLength: {len}"),
-            Self::DoesNotExist => write!(f, "ID does not exist"),
+            Self::DoesNotExist { input, suggestions } if suggestions.is_empty() => {
+                write!(f, "ID '{input}' does not exist")
+            }
+            Self::DoesNotExist { input, suggestions } => write!(
+                f,
+                "ID '{input}' does not exist, did you mean {}?",
+                suggestions
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             Self::InvalidEncoding => write!(f, "ID has invalid encoding"),
+            Self::InvalidIndex(index) => write!(f, "{index} is not a valid territory index"),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for TerritoryIdError {}
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+    diesel(sql_type = diesel::sql_types::Text)
+)]
 pub struct TerritoryId([u8; 3]);
 
-impl std::fmt::Debug for TerritoryId {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        unsafe { write!(f, "TerritoryId({})", std::str::from_utf8_unchecked(&self.0)) }
+impl core::fmt::Debug for TerritoryId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        unsafe {
+            write!(
+                f,
+                "TerritoryId({})",
+                core::str::from_utf8_unchecked(&self.0)
+            )
+        }
     }
 }
 
 impl phf::PhfHash for TerritoryId {
-    fn phf_hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    fn phf_hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.0.phf_hash(state)
     }
 }
@@ -50,7 +159,11 @@ impl phf_shared::PhfBorrow<TerritoryId> for TerritoryId {
     }
 }
 
-impl std::str::FromStr for TerritoryId {
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Parses territory ids strictly, as used by all wire formats and database integrations. Input
+/// typed by users should go through [`TerritoryId::parse_lenient`] instead.
+impl core::str::FromStr for TerritoryId {
     type Err = TerritoryIdError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -68,16 +181,46 @@ impl std::str::FromStr for TerritoryId {
         let id = Self(id_bytes);
 
         if !TERRITORY_INFO.contains_key(&id) {
-            Err(TerritoryIdError::DoesNotExist)
+            Err(TerritoryIdError::DoesNotExist {
+                input: s.to_owned(),
+                suggestions: Vec::new(),
+            })
         } else {
             Ok(id)
         }
     }
 }
 
-impl std::fmt::Display for TerritoryId {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        unsafe { write!(f, "{}", std::str::from_utf8_unchecked(&self.0)) }
+/// Optimal string alignment distance, i.e. Levenshtein distance which also counts swapping two
+/// adjacent letters as a single edit.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+impl core::fmt::Display for TerritoryId {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        unsafe { write!(f, "{}", core::str::from_utf8_unchecked(&self.0)) }
     }
 }
 
@@ -91,7 +234,7 @@ impl<'de> serde::Deserialize<'de> for TerritoryId {
         impl<'de> serde::de::Visitor<'de> for IdVisitor {
             type Value = TerritoryId;
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
                 write!(formatter, "struct TerritoryId")
             }
 
@@ -99,24 +242,13 @@ impl<'de> serde::Deserialize<'de> for TerritoryId {
             where
                 E: serde::de::Error,
             {
-                if !v.is_ascii() || v.as_bytes().len() != 3 {
-                    return Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Str(v),
-                        &"A valid three letter territory ID",
-                    ));
-                }
-
-                let mut id_bytes = [0; 3];
-                id_bytes.copy_from_slice(v.as_bytes());
-
-                let id = TerritoryId(id_bytes);
-                if !TERRITORY_INFO.contains_key(&id) {
-                    return Err(serde::de::Error::invalid_value(
+                v.parse().map_err(|why| match why {
+                    TerritoryIdError::DoesNotExist { .. } => serde::de::Error::custom(why),
+                    _ => serde::de::Error::invalid_value(
                         serde::de::Unexpected::Str(v),
                         &"A valid three letter territory ID",
-                    ));
-                }
-                Ok(id)
+                    ),
+                })
             }
         }
 
@@ -130,7 +262,7 @@ impl serde::Serialize for TerritoryId {
     where
         S: serde::Serializer,
     {
-        serde::Serialize::serialize(&self.to_string(), serializer)
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -138,54 +270,87 @@ impl serde::Serialize for TerritoryId {
 impl<DB> sqlx::Type<DB> for TerritoryId
 where
     DB: sqlx::Database,
-    String: sqlx::Type<DB>,
+    str: sqlx::Type<DB>,
 {
     fn type_info() -> DB::TypeInfo {
-        <String as sqlx::Type<DB>>::type_info()
+        <str as sqlx::Type<DB>>::type_info()
     }
 
     fn compatible(ty: &<DB as sqlx::Database>::TypeInfo) -> bool {
-        <String as sqlx::Type<DB>>::compatible(ty)
+        <str as sqlx::Type<DB>>::compatible(ty)
     }
 }
 
 #[cfg(feature = "sqlx")]
-impl<DB> sqlx::Decode<'_, DB> for TerritoryId
+impl<'r, DB> sqlx::Decode<'r, DB> for TerritoryId
 where
     DB: sqlx::Database,
-    String: for<'r> sqlx::Decode<'r, DB>,
+    &'r str: sqlx::Decode<'r, DB>,
 {
     fn decode(
-        value: <DB as sqlx::database::HasValueRef<'_>>::ValueRef,
+        value: <DB as sqlx::database::HasValueRef<'r>>::ValueRef,
     ) -> Result<Self, sqlx::error::BoxDynError> {
-        let value = <String as sqlx::Decode<'_, DB>>::decode(value)?;
+        let value = <&str as sqlx::Decode<'r, DB>>::decode(value)?;
         Ok(value.parse()?)
     }
 }
 
 #[cfg(feature = "sqlx")]
-impl<DB> sqlx::Encode<'_, DB> for TerritoryId
+impl<'q, DB> sqlx::Encode<'q, DB> for TerritoryId
 where
     DB: sqlx::Database,
-    String: for<'q> sqlx::Encode<'q, DB>,
+    &'q str: sqlx::Encode<'q, DB>,
 {
     fn encode_by_ref(
         &self,
-        buf: &mut <DB as sqlx::database::HasArguments<'_>>::ArgumentBuffer,
+        buf: &mut <DB as sqlx::database::HasArguments<'q>>::ArgumentBuffer,
     ) -> sqlx::encode::IsNull {
-        let value = self.to_string();
-        <String as sqlx::Encode<'_, DB>>::encode(value, buf)
+        <&str as sqlx::Encode<'q, DB>>::encode(self.as_str(), buf)
     }
 
-    fn encode(
-        self,
-        buf: &mut <DB as sqlx::database::HasArguments<'_>>::ArgumentBuffer,
-    ) -> sqlx::encode::IsNull
-    where
-        Self: Sized,
-    {
-        let value = self.to_string();
-        <String as sqlx::Encode<'_, DB>>::encode(value, buf)
+    fn size_hint(&self) -> usize {
+        <&str as sqlx::Encode<'q, DB>>::size_hint(&self.as_str())
+    }
+}
+
+/// Allows binding `Vec<TerritoryId>` and `&[TerritoryId]`, which are compatible with both `text[]`
+/// and `char(3)[]` columns.
+#[cfg(feature = "sqlx-postgres")]
+impl sqlx::postgres::PgHasArrayType for TerritoryId {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        <&str as sqlx::postgres::PgHasArrayType>::array_type_info()
+    }
+
+    fn array_compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+        <&str as sqlx::postgres::PgHasArrayType>::array_compatible(ty)
+    }
+}
+
+/// Covers `TEXT`, `VARCHAR` and `CHAR(3)` (`Bpchar`) columns on all backends.
+#[cfg(feature = "diesel")]
+impl<DB> diesel::serialize::ToSql<diesel::sql_types::Text, DB> for TerritoryId
+where
+    DB: diesel::backend::Backend,
+    str: diesel::serialize::ToSql<diesel::sql_types::Text, DB>,
+{
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, DB>,
+    ) -> diesel::serialize::Result {
+        <str as diesel::serialize::ToSql<diesel::sql_types::Text, DB>>::to_sql(self.as_str(), out)
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl<DB> diesel::deserialize::FromSql<diesel::sql_types::Text, DB> for TerritoryId
+where
+    DB: diesel::backend::Backend,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> diesel::deserialize::Result<Self> {
+        let value =
+            <String as diesel::deserialize::FromSql<diesel::sql_types::Text, DB>>::from_sql(bytes)?;
+        Ok(value.parse()?)
     }
 }
 
@@ -244,310 +409,290 @@ impl sea_orm::sea_query::Nullable for TerritoryId {
     }
 }
 
-impl TerritoryId {
-    pub fn info(&self) -> &TerritoryInfo {
-        TERRITORY_INFO.get(self).unwrap()
+/// Allows using `TerritoryId` as a primary key, which must not be auto incrementing.
+#[cfg(feature = "sea-orm")]
+impl sea_orm::TryFromU64 for TerritoryId {
+    fn try_from_u64(_n: u64) -> Result<Self, sea_orm::DbErr> {
+        Err(sea_orm::DbErr::ConvertFromU64(
+            "TerritoryId can not be constructed from a u64, primary keys of this type must not be \
+             auto incrementing",
+        ))
     }
 }
 
-pub struct TerritoryInfo {
-    pub shape: &'static [svgtypes::SimplePathSegment],
-    pub sector: u8,
-    pub db_id: i32,
-    pub slots: u16,
-    pub neighbors: &'static [TerritoryId],
-}
-
-include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
+#[cfg(feature = "sea-orm-postgres-array")]
+impl sea_orm::sea_query::value::with_array::NotU8 for TerritoryId {}
 
-pub const MAP_WIDTH: u32 = 6_256;
-pub const MAP_HEIGHT: u32 = 3_648;
-pub const MAP_BBOX: image::math::Rect = image::math::Rect {
-    x: 0,
-    y: 0,
-    width: MAP_WIDTH,
-    height: MAP_HEIGHT,
-};
-const TILE_WIDTH: u32 = 600;
-const TILE_HEIGHT: u32 = 400;
+#[cfg(feature = "sea-orm-postgres-array")]
+impl sea_orm::TryGetableArray for TerritoryId {
+    fn try_get_by<I: sea_orm::ColIdx>(
+        res: &sea_orm::QueryResult,
+        index: I,
+    ) -> Result<Vec<Self>, sea_orm::TryGetError> {
+        let values = <Vec<String> as sea_orm::TryGetable>::try_get_by(res, index)?;
+        values
+            .iter()
+            .map(|value| value.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|why| {
+                sea_orm::TryGetError::DbErr(sea_orm::DbErr::TryIntoErr {
+                    from: "Vec<String>",
+                    into: "Vec<TerritoryId>",
+                    source: Box::new(why),
+                })
+            })
+    }
+}
 
-#[derive(RustEmbed)]
-#[folder = "static/map_tiles"]
-#[include = "*.tiff"]
-struct MapTiles;
+#[cfg(feature = "sea-orm")]
+impl TerritoryId {
+    /// Definition of a `CHAR(3) NOT NULL` column for use in migrations.
+    pub fn column_def(name: impl sea_orm::sea_query::IntoIden) -> sea_orm::sea_query::ColumnDef {
+        let mut def = sea_orm::sea_query::ColumnDef::new_with_type(
+            name,
+            <Self as sea_orm::sea_query::ValueType>::column_type(),
+        );
+        def.not_null();
+        def
+    }
 
-pub fn path_for_territory(id: TerritoryId) -> Option<usvg::tiny_skia_path::Path> {
-    let instructions = id.info().shape;
+    /// Definition of a `CHAR(3)[] NOT NULL` column for use in migrations.
+    #[cfg(feature = "sea-orm-postgres-array")]
+    pub fn array_column_def(
+        name: impl sea_orm::sea_query::IntoIden,
+    ) -> sea_orm::sea_query::ColumnDef {
+        let mut def = sea_orm::sea_query::ColumnDef::new(name);
+        def.array(<Self as sea_orm::sea_query::ValueType>::column_type())
+            .not_null();
+        def
+    }
+}
 
-    let mut builder = usvg::tiny_skia_path::PathBuilder::new();
+/// Pattern that all territory IDs match, used in generated schemas.
+#[cfg(any(feature = "schemars", feature = "utoipa"))]
+const ID_PATTERN: &str = "^[A-Z]{3}$";
+
+/// All valid IDs in alphabetical order, which are listed in generated schemas if the
+/// `schema-enum` feature is enabled.
+#[cfg(any(feature = "schemars", feature = "utoipa"))]
+fn schema_enum_values() -> Option<Vec<&'static str>> {
+    cfg!(feature = "schema-enum").then(|| {
+        SORTED_TERRITORY_IDS
+            .iter()
+            .map(|id| TerritoryId(*id).as_str())
+            .collect()
+    })
+}
 
-    for inst in instructions {
-        match inst {
-            SimplePathSegment::MoveTo { x, y } => {
-                builder.move_to(*x as f32, *y as f32);
-            }
-            SimplePathSegment::LineTo { x, y } => {
-                builder.line_to(*x as f32, *y as f32);
-            }
-            SimplePathSegment::Quadratic { x1, y1, x, y } => {
-                builder.quad_to(*x1 as f32, *y1 as f32, *x as f32, *y as f32);
-            }
-            SimplePathSegment::CurveTo {
-                x1,
-                y1,
-                x2,
-                y2,
-                x,
-                y,
-            } => {
-                builder.cubic_to(
-                    *x1 as f32, *y1 as f32, *x2 as f32, *y2 as f32, *x as f32, *y as f32,
-                );
-            }
-            SimplePathSegment::ClosePath => {
-                builder.close();
+/// A three letter territory ID, which must exist in the embedded dataset.
+#[cfg(feature = "async-graphql")]
+#[async_graphql::Scalar(name = "TerritoryId")]
+impl async_graphql::ScalarType for TerritoryId {
+    fn parse(value: async_graphql::Value) -> async_graphql::InputValueResult<Self> {
+        match &value {
+            async_graphql::Value::String(s) => {
+                s.parse().map_err(async_graphql::InputValueError::custom)
             }
+            _ => Err(async_graphql::InputValueError::expected_type(value)),
         }
     }
 
-    builder.finish()
-}
+    fn is_valid(value: &async_graphql::Value) -> bool {
+        matches!(value, async_graphql::Value::String(s) if s.parse::<Self>().is_ok())
+    }
 
-pub fn bbox_for_path(path: &usvg::tiny_skia_path::Path, factor: f32, ar: f32) -> Rect {
-    let bounds = path.bounds();
+    fn to_value(&self) -> async_graphql::Value {
+        async_graphql::Value::String(self.as_str().to_owned())
+    }
+}
 
-    let bounds_ar = bounds.width() / bounds.height();
-    if bounds_ar > ar {
-        let width = bounds.width() / factor;
-        let height = width / ar;
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for TerritoryId {
+    fn schema_name() -> String {
+        stringify!(TerritoryId).to_owned()
+    }
 
-        let x = bounds.x() - (width * (1f32 - factor) / 2f32);
-        let y = bounds.y() - (height - bounds.height()) / 2f32;
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            metadata: Some(Box::new(schemars::schema::Metadata {
+                description: Some("A three letter territory ID".to_owned()),
+                ..Default::default()
+            })),
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            enum_values: schema_enum_values()
+                .map(|ids| ids.into_iter().map(serde_json::Value::from).collect()),
+            string: Some(Box::new(schemars::schema::StringValidation {
+                max_length: Some(3),
+                min_length: Some(3),
+                pattern: Some(ID_PATTERN.to_owned()),
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
 
-        Rect::from_xywh(x, y, width, height).unwrap()
-    } else {
-        let height = bounds.height() / factor;
-        let width = height * ar;
+#[cfg(feature = "utoipa")]
+impl<'s> utoipa::ToSchema<'s> for TerritoryId {
+    fn schema() -> (
+        &'s str,
+        utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>,
+    ) {
+        (
+            stringify!(TerritoryId),
+            utoipa::openapi::ObjectBuilder::new()
+                .schema_type(utoipa::openapi::SchemaType::String)
+                .description(Some("A three letter territory ID"))
+                .min_length(Some(3))
+                .max_length(Some(3))
+                .pattern(Some(ID_PATTERN))
+                .enum_values(schema_enum_values())
+                .example(Some("XOD".into()))
+                .into(),
+        )
+    }
+}
 
-        let y = bounds.y() - (height * (1f32 - factor) / 2f32);
-        let x = bounds.x() - (width - bounds.width()) / 2f32;
+impl TerritoryId {
+    /// The three letters of the ID, borrowed from the embedded dataset.
+    pub fn as_str(&self) -> &'static str {
+        let id = TERRITORY_INFO.get_key(self).unwrap();
+        // IDs are validated to be ASCII in `build.rs`
+        unsafe { core::str::from_utf8_unchecked(&id.0) }
+    }
 
-        Rect::from_xywh(x, y, width, height).unwrap()
+    /// Parses an id typed by a user, ignoring surrounding whitespace and case. Unknown ids come
+    /// with suggestions of close ids from the embedded dataset.
+    pub fn parse_lenient(s: &str) -> Result<Self, TerritoryIdError> {
+        TerritoryDataset::embedded().parse_id(s)
     }
-}
 
-pub fn colour_from_hex(hex: &str) -> Option<usvg::Color> {
-    if !hex.starts_with('#') || hex.len() != 7 {
-        return None;
+    pub fn info(&self) -> &'static TerritoryInfo {
+        TERRITORY_INFO.get(self).unwrap()
     }
 
-    let r = u8::from_str_radix(&hex[1..=2], 16).ok()?;
-    let g = u8::from_str_radix(&hex[3..=4], 16).ok()?;
-    let b = u8::from_str_radix(&hex[5..=6], 16).ok()?;
+    pub fn record(&self) -> TerritoryRecord<'static> {
+        TerritoryRecord {
+            id: *self,
+            info: self.info(),
+        }
+    }
 
-    Some(usvg::Color::new_rgb(r, g, b))
-}
+    /// Const version of [`str::parse`], which panics if `s` is not a valid territory id. When used
+    /// in a const context this turns typos into compile errors, see [`territory!`].
+    pub const fn from_str_const(s: &str) -> Self {
+        let bytes = s.as_bytes();
+        if bytes.len() != 3 {
+            panic!("territory ids have to be exactly three letters long");
+        }
+        let id = [bytes[0], bytes[1], bytes[2]];
 
-pub fn element_for_territory(
-    id: TerritoryId,
-    fill: Option<usvg::Fill>,
-    stroke: Option<usvg::Stroke>,
-) -> Option<usvg::Path> {
-    Some(usvg::Path {
-        id: "".to_owned(),
-        transform: usvg::Transform::identity(),
-        visibility: usvg::Visibility::Visible,
-        fill,
-        stroke,
-        paint_order: usvg::PaintOrder::FillAndStroke,
-        rendering_mode: usvg::ShapeRendering::CrispEdges,
-        text_bbox: None,
-        data: Rc::new(path_for_territory(id)?),
-    })
-}
+        if sorted_position(&id).is_none() {
+            panic!("territory id does not exist");
+        }
 
-pub fn fit_view_box(bbox: Rect) -> image::math::Rect {
-    let width = (bbox.width() as u32).min(MAP_WIDTH);
-    let height = (bbox.height() as u32).min(MAP_HEIGHT);
-    let x = (bbox.x() as u32).clamp(0, MAP_WIDTH - width);
-    let y = (bbox.y() as u32).clamp(0, MAP_HEIGHT - height);
+        Self(id)
+    }
 
-    image::math::Rect {
-        x,
-        y,
-        width,
-        height,
+    /// Upper bound of all territory indices.
+    pub const INDEX_LEN: usize = TERRITORY_INDEX_LEN;
+
+    /// A dense index in `0..TerritoryId::INDEX_LEN` which can be used as a compact representation
+    /// of the id, e.g. for storage.
+    ///
+    /// Indices are assigned through `static/territory_index.txt` and are guaranteed to never
+    /// change for existing ids across dataset updates. The indices of territories which were
+    /// removed from the game are not reused.
+    pub const fn index(&self) -> u16 {
+        match sorted_position(&self.0) {
+            Some(pos) => SORTED_TERRITORY_INDICES[pos],
+            None => unreachable!(),
+        }
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct RenderInstruction {
-    pub colour: usvg::Color,
-    pub opacity: f32,
+    pub fn from_index(index: u16) -> Option<Self> {
+        TERRITORY_INDEX.get(index as usize).copied().flatten()
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum RenderScale {
-    X1,
-    X4,
-}
-
-pub fn render_territories(
-    view_port: image::math::Rect,
-    fill: HashMap<TerritoryId, RenderInstruction>,
-    mut stroke: HashMap<TerritoryId, RenderInstruction>,
-    scale: RenderScale,
-) -> image::RgbaImage {
-    let root = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
-        id: "".to_owned(),
-        transform: usvg::Transform::identity(),
-        opacity: usvg::NormalizedF32::ONE,
-        blend_mode: usvg::BlendMode::Normal,
-        isolate: false,
-        clip_path: None,
-        mask: None,
-        filters: vec![],
-    }));
-
-    for (id, inst) in &fill {
-        let border = stroke.remove(id).map(|i| usvg::Stroke {
-            paint: usvg::Paint::Color(i.colour),
-            dasharray: None,
-            dashoffset: 0f32,
-            miterlimit: usvg::StrokeMiterlimit::new(4f32),
-            opacity: usvg::NormalizedF32::new(i.opacity).unwrap(),
-            width: usvg::NonZeroPositiveF32::new(4f32).unwrap(),
-            linecap: usvg::LineCap::Butt,
-            linejoin: usvg::LineJoin::Miter,
-        });
-
-        let fill = Some(usvg::Fill {
-            paint: usvg::Paint::Color(inst.colour),
-            opacity: usvg::NormalizedF32::new(inst.opacity).unwrap(),
-            rule: usvg::FillRule::NonZero,
-        });
-
-        let path = element_for_territory(*id, fill, border).unwrap();
-        root.append_kind(usvg::NodeKind::Path(path));
-    }
-
-    for (id, inst) in stroke {
-        let border = Some(usvg::Stroke {
-            paint: usvg::Paint::Color(inst.colour),
-            dasharray: None,
-            dashoffset: 0f32,
-            miterlimit: usvg::StrokeMiterlimit::new(4f32),
-            opacity: usvg::NormalizedF32::new(inst.opacity).unwrap(),
-            width: usvg::NonZeroPositiveF32::new(4f32).unwrap(),
-            linecap: usvg::LineCap::Butt,
-            linejoin: usvg::LineJoin::Miter,
-        });
-
-        let path = element_for_territory(id, None, border).unwrap();
-        root.append_kind(usvg::NodeKind::Path(path));
-    }
-
-    let scale_factor = match scale {
-        RenderScale::X1 => 1,
-        RenderScale::X4 => 4,
-    };
-
-    let scaled_width = view_port.width / scale_factor;
-    let scaled_height = view_port.height / scale_factor;
-
-    let tree = resvg::Tree::from_usvg(&usvg::Tree {
-        size: usvg::Size::from_wh(scaled_width as f32, scaled_height as f32).unwrap(),
-        view_box: usvg::ViewBox {
-            rect: usvg::NonZeroRect::from_xywh(
-                view_port.x as f32,
-                view_port.y as f32,
-                view_port.width as f32,
-                view_port.height as f32,
-            )
-            .unwrap(),
-            aspect: usvg::AspectRatio::default(),
-        },
-        root,
-    });
-
-    let mut pixmap = resvg::tiny_skia::Pixmap::new(scaled_width, scaled_height).unwrap();
-    tree.render(
-        resvg::tiny_skia::Transform::identity(),
-        &mut pixmap.as_mut(),
-    );
-
-    let shapes = image::RgbaImage::from_raw(scaled_width, scaled_height, pixmap.take()).unwrap();
-    let mut background = match scale {
-        RenderScale::X1 => {
-            load_map_segment(view_port.x, view_port.y, view_port.width, view_port.height).convert()
+/// Position of `id` in [`SORTED_TERRITORY_IDS`].
+const fn sorted_position(id: &[u8; 3]) -> Option<usize> {
+    let mut low = 0;
+    let mut high = SORTED_TERRITORY_IDS.len();
+    while low < high {
+        let mid = (low + high) / 2;
+        let candidate = SORTED_TERRITORY_IDS[mid];
+
+        let mut i = 0;
+        while i < 3 && candidate[i] == id[i] {
+            i += 1;
         }
-        RenderScale::X4 => crop(
-            &mut load_map_x4(),
-            view_port.x / 4,
-            view_port.y / 4,
-            scaled_width,
-            scaled_height,
-        )
-        .to_image()
-        .convert(),
-    };
-
-    overlay(&mut background, &shapes, 0, 0);
-
-    background
-}
-
-pub fn load_map_segment(x: u32, y: u32, w: u32, h: u32) -> GrayImage {
-    let mut image = GrayImage::new(w, h);
-    let mut cursor = (x, y);
-
-    while cursor.1 < (y + h) {
-        let x_tile = cursor.0 / TILE_WIDTH + 1;
-        let y_tile = cursor.1 / TILE_HEIGHT + 1;
-        let x_min = cursor.0 % TILE_WIDTH;
-        let y_min = cursor.1 % TILE_HEIGHT;
-        let width = ((x + w) - cursor.0).min(TILE_WIDTH - (cursor.0 % TILE_WIDTH));
-        let height = ((y + h) - cursor.1).min(TILE_HEIGHT - (cursor.1 % TILE_HEIGHT));
-
-        let tile = MapTiles::get(&format!("map_{x_tile}_{y_tile}.tiff")).unwrap();
-
-        let decoder = TiffDecoder::new(Cursor::new(tile.data)).unwrap();
-        assert!(decoder.color_type() == ColorType::L8);
-        let mut buf = vec![0; decoder.total_bytes() as usize];
-        let (d_x, d_y) = decoder.dimensions();
-        decoder.read_image(&mut buf).unwrap();
-
-        let tile = GrayImage::from_raw(d_x, d_y, buf).unwrap();
-        let view = tile.view(x_min, y_min, width, height);
-        replace(
-            &mut image,
-            view.deref(),
-            (cursor.0 - x) as i64,
-            (cursor.1 - y) as i64,
-        );
 
-        if cursor.0 + width >= x + w {
-            cursor = (x, cursor.1 + height);
+        if i == 3 {
+            return Some(mid);
+        } else if candidate[i] < id[i] {
+            low = mid + 1;
         } else {
-            cursor.0 += width;
+            high = mid;
         }
     }
 
-    image
+    None
+}
+
+/// Creates a [`TerritoryId`] which is checked against the embedded dataset at compile time.
+///
+/// ```
+/// let xod = torn_territories::territory!("XOD");
+/// assert_eq!(xod, "XOD".parse().unwrap());
+/// ```
+///
+/// ```compile_fail
+/// let invalid = torn_territories::territory!("ZZZ");
+/// ```
+#[macro_export]
+macro_rules! territory {
+    ($id:literal) => {{
+        const ID: $crate::TerritoryId = $crate::TerritoryId::from_str_const($id);
+        ID
+    }};
 }
 
-pub fn load_map_x4() -> GrayImage {
-    let img = MapTiles::get("map_x4.tiff").unwrap();
+/// Metadata of a territory. With the `serde` feature it is (de)serialised in the same schema as
+/// the entries of `territory_shapes.json`, with the shape as an SVG path string.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerritoryInfo {
+    #[cfg_attr(feature = "serde", serde(with = "serde_helpers::shape"))]
+    pub shape: Cow<'static, [PathSegment]>,
+    pub sector: u8,
+    pub db_id: i32,
+    pub slots: u16,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub neighbors: Cow<'static, [TerritoryId]>,
+}
 
-    let decoder = TiffDecoder::new(Cursor::new(img.data)).unwrap();
-    assert!(decoder.color_type() == ColorType::L8);
-    let mut buf = vec![0; decoder.total_bytes() as usize];
-    let (d_x, d_y) = decoder.dimensions();
-    decoder.read_image(&mut buf).unwrap();
+/// A territory together with its metadata, e.g. for returning it from an API.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TerritoryRecord<'a> {
+    pub id: TerritoryId,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub info: &'a TerritoryInfo,
+}
 
-    GrayImage::from_raw(d_x, d_y, buf).unwrap()
+impl TerritoryInfo {
+    /// The shape as an SVG path string, using absolute coordinates.
+    pub fn svg_path(&self) -> String {
+        shape::svg_path(&self.shape)
+    }
 }
 
+include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
+
+pub const MAP_WIDTH: u32 = 6_256;
+pub const MAP_HEIGHT: u32 = 3_648;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -557,6 +702,66 @@ mod tests {
         let _id: TerritoryId = "XOD".parse().unwrap();
     }
 
+    #[test]
+    fn test_parse_normalised() {
+        let id: TerritoryId = "XOD".parse().unwrap();
+        assert_eq!(TerritoryId::parse_lenient(" xod\n").unwrap(), id);
+        assert_eq!(TerritoryId::parse_lenient("xOd").unwrap(), id);
+
+        // wire formats stay strict
+        assert!(" XOD".parse::<TerritoryId>().is_err());
+        assert!("xod".parse::<TerritoryId>().is_err());
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let Err(TerritoryIdError::DoesNotExist { input, suggestions }) =
+            TerritoryId::parse_lenient(" xdo")
+        else {
+            panic!("XDO should not exist");
+        };
+
+        assert_eq!(input, "xdo");
+        assert_eq!(suggestions.len(), MAX_SUGGESTIONS);
+        assert_eq!(suggestions[0], "XOD".parse().unwrap());
+
+        // the strict parser does not look for suggestions
+        let Err(TerritoryIdError::DoesNotExist { suggestions, .. }) = "XDO".parse::<TerritoryId>()
+        else {
+            panic!("XDO should not exist");
+        };
+        assert!(suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_const_id() {
+        const XOD: TerritoryId = territory!("XOD");
+        assert_eq!(XOD, "XOD".parse().unwrap());
+
+        for id in TerritoryDataset::embedded().ids() {
+            assert_eq!(TerritoryId::from_str_const(&id.to_string()), id);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_info_json() {
+        let record = TerritoryId::from_str_const("XOD").record();
+        let value = serde_json::to_value(record).unwrap();
+
+        assert_eq!(value["id"], "XOD");
+        assert_eq!(value["db_id"], record.info.db_id);
+        assert_eq!(
+            value["neighbors"].as_array().unwrap().len(),
+            record.info.neighbors.len()
+        );
+        assert!(value["shape"].as_str().unwrap().starts_with('M'));
+
+        let info: TerritoryInfo = serde_json::from_value(value).unwrap();
+        assert_eq!(info.shape, record.info.shape);
+        assert_eq!(info.neighbors, record.info.neighbors);
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_json_value() {
@@ -565,4 +770,238 @@ mod tests {
         let value = serde_json::to_value(TerritoryId::from_str("GVE").unwrap()).unwrap();
         let _id: TerritoryId = serde_json::from_value(value).unwrap();
     }
+
+    #[test]
+    fn test_as_str() {
+        for id in TerritoryDataset::embedded().ids() {
+            assert_eq!(id.as_str(), id.to_string());
+        }
+    }
+
+    #[cfg(feature = "sqlx")]
+    #[tokio::test]
+    async fn test_sqlx_sqlite() {
+        let mut conn = <sqlx::SqliteConnection as sqlx::Connection>::connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query("CREATE TABLE territories (id CHAR(3) NOT NULL, idx SMALLINT NOT NULL)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let id = territory!("XOD");
+        sqlx::query("INSERT INTO territories (id, idx) VALUES (?, ?)")
+            .bind(id)
+            .bind(TerritoryIndex(id))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let (by_id, by_index): (TerritoryId, TerritoryIndex) =
+            sqlx::query_as("SELECT id, idx FROM territories")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap();
+        assert_eq!(by_id, id);
+        assert_eq!(by_index.0, id);
+
+        let invalid: Result<TerritoryId, _> = sqlx::query_scalar("SELECT 'ZZZ'")
+            .fetch_one(&mut conn)
+            .await;
+        assert!(invalid.is_err());
+    }
+
+    #[cfg(feature = "diesel")]
+    #[test]
+    fn test_diesel_sqlite() {
+        use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+        diesel::table! {
+            territories (id) {
+                id -> Text,
+                sector -> Integer,
+            }
+        }
+
+        #[derive(diesel::prelude::Queryable, diesel::prelude::Insertable, Debug, PartialEq)]
+        #[diesel(table_name = territories)]
+        struct Row {
+            id: TerritoryId,
+            sector: i32,
+        }
+
+        let mut conn = diesel::SqliteConnection::establish(":memory:").unwrap();
+        diesel::sql_query(
+            "CREATE TABLE territories (id CHAR(3) PRIMARY KEY, sector INTEGER NOT NULL)",
+        )
+        .execute(&mut conn)
+        .unwrap();
+
+        let rows: Vec<_> = [territory!("XOD"), territory!("GVE"), territory!("AAB")]
+            .into_iter()
+            .map(|id| Row {
+                id,
+                sector: id.info().sector.into(),
+            })
+            .collect();
+        diesel::insert_into(territories::table)
+            .values(&rows)
+            .execute(&mut conn)
+            .unwrap();
+
+        let found: Vec<Row> = territories::table
+            .filter(territories::id.eq_any([territory!("GVE"), territory!("XOD")]))
+            .order(territories::id)
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                Row {
+                    id: territory!("GVE"),
+                    sector: territory!("GVE").info().sector.into()
+                },
+                Row {
+                    id: territory!("XOD"),
+                    sector: territory!("XOD").info().sector.into()
+                },
+            ]
+        );
+
+        let invalid = diesel::select(diesel::dsl::sql::<diesel::sql_types::Text>("'ZZZ'"))
+            .get_result::<TerritoryId>(&mut conn);
+        assert!(invalid.is_err());
+    }
+
+    #[cfg(feature = "sea-orm")]
+    #[tokio::test]
+    async fn test_sea_orm_sqlite() {
+        use sea_orm::{
+            sea_query::{Alias, Table},
+            ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set,
+        };
+
+        mod territory {
+            use sea_orm::entity::prelude::*;
+
+            #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+            #[sea_orm(table_name = "territories")]
+            pub struct Model {
+                #[sea_orm(primary_key, auto_increment = false)]
+                pub id: crate::TerritoryId,
+                pub sector: i32,
+            }
+
+            #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+            pub enum Relation {}
+
+            impl ActiveModelBehavior for ActiveModel {}
+        }
+
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let create = Table::create()
+            .table(Alias::new("territories"))
+            .col(TerritoryId::column_def(Alias::new("id")).primary_key())
+            .col(
+                sea_orm::sea_query::ColumnDef::new(Alias::new("sector"))
+                    .integer()
+                    .not_null(),
+            )
+            .to_owned();
+        db.execute(db.get_database_backend().build(&create))
+            .await
+            .unwrap();
+
+        for id in [territory!("XOD"), territory!("GVE"), territory!("AAB")] {
+            territory::ActiveModel {
+                id: Set(id),
+                sector: Set(id.info().sector.into()),
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let xod = territory::Entity::find_by_id(territory!("XOD"))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(xod.id, territory!("XOD"));
+
+        let found = territory::Entity::find()
+            .filter(territory::Column::Id.is_in([territory!("GVE"), territory!("AAB")]))
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+
+        assert!(<TerritoryId as sea_orm::TryFromU64>::try_from_u64(1).is_err());
+    }
+
+    #[cfg(feature = "async-graphql")]
+    #[tokio::test]
+    async fn test_graphql_scalar() {
+        struct Query;
+
+        #[async_graphql::Object]
+        impl Query {
+            async fn echo(&self, id: TerritoryId) -> TerritoryId {
+                id
+            }
+        }
+
+        let schema = async_graphql::Schema::new(
+            Query,
+            async_graphql::EmptyMutation,
+            async_graphql::EmptySubscription,
+        );
+
+        let res = schema.execute(r#"{ echo(id: "xod") }"#).await;
+        assert!(res.errors.is_empty());
+        assert_eq!(res.data, async_graphql::value!({ "echo": "XOD" }));
+
+        let res = schema.execute(r#"{ echo(id: "ZZZ") }"#).await;
+        assert!(!res.errors.is_empty());
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_json_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(TerritoryId)).unwrap();
+        assert_eq!(schema["pattern"], ID_PATTERN);
+        assert_eq!(schema.get("enum").is_some(), cfg!(feature = "schema-enum"));
+        if let Some(ids) = schema.get("enum") {
+            assert_eq!(ids.as_array().unwrap().len(), TERRITORY_INFO.len());
+        }
+    }
+
+    #[cfg(feature = "utoipa")]
+    #[test]
+    fn test_openapi_schema() {
+        let (name, schema) = <TerritoryId as utoipa::ToSchema>::schema();
+        assert_eq!(name, "TerritoryId");
+
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(schema["type"], "string");
+        assert_eq!(schema["pattern"], ID_PATTERN);
+    }
+
+    #[cfg(feature = "sqlx-postgres")]
+    #[test]
+    fn test_sqlx_postgres_array() {
+        use sqlx::{postgres::PgTypeInfo, Postgres, Type};
+
+        assert_eq!(
+            <Vec<TerritoryId> as Type<Postgres>>::type_info(),
+            PgTypeInfo::with_name("_text")
+        );
+        assert!(<Vec<TerritoryId> as Type<Postgres>>::compatible(&<Vec<
+            String,
+        > as Type<
+            Postgres,
+        >>::type_info(
+        )));
+    }
 }
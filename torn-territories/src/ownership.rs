@@ -0,0 +1,620 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use image::{imageops::overlay, Rgba, RgbaImage};
+use resvg::usvg;
+
+#[cfg(feature = "serde")]
+use crate::serde_helpers;
+#[cfg(feature = "embedded-tiles")]
+use crate::EmbeddedTiles;
+use crate::{
+    colour_from_hex, fit_view_box,
+    render::render_background,
+    text::{draw_text, fill_rect, text_size},
+    OwnerId, OwnershipDiff, RenderInstruction, RenderScale, TerritoryDataset, TerritoryMap,
+    TerritorySet, TileError, TileSource, MAP_BBOX,
+};
+
+const FILL_OPACITY: f32 = 0.55;
+const STROKE_OPACITY: f32 = 0.9;
+
+const LEGEND_SCALE: u32 = 2;
+const LEGEND_PADDING: u32 = 6;
+const MAX_LEGEND_ENTRIES: usize = 12;
+
+/// How an owner is drawn on the map and labelled in the legend.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnerStyle {
+    #[cfg_attr(feature = "serde", serde(with = "serde_helpers::colour"))]
+    pub colour: usvg::Color,
+    /// Shown in the legend instead of the owner ID.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub label: Option<String>,
+}
+
+/// Who owns which territory at one point in time, together with how each owner is drawn.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnershipSnapshot {
+    pub owners: TerritoryMap<OwnerId>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub styles: BTreeMap<OwnerId, OwnerStyle>,
+}
+
+/// The default colour of an owner, which only depends on its ID. Consecutive IDs are spread
+/// around the hue circle by the golden angle and alternate in lightness.
+pub fn auto_colour(owner: OwnerId) -> usvg::Color {
+    const GOLDEN_ANGLE: f64 = 137.507_764;
+    const LIGHTNESS: [f32; 3] = [0.5, 0.36, 0.64];
+
+    let hue = (f64::from(owner) * GOLDEN_ANGLE) % 360f64;
+    let lightness = LIGHTNESS[owner as usize % LIGHTNESS.len()];
+    hsl_to_rgb(hue as f32, 0.75, lightness)
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> usvg::Color {
+    let chroma = (1f32 - (2f32 * lightness - 1f32).abs()) * saturation;
+    let sector = hue / 60f32;
+    let x = chroma * (1f32 - (sector % 2f32 - 1f32).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0f32),
+        1 => (x, chroma, 0f32),
+        2 => (0f32, chroma, x),
+        3 => (0f32, x, chroma),
+        4 => (x, 0f32, chroma),
+        _ => (chroma, 0f32, x),
+    };
+    let m = lightness - chroma / 2f32;
+    let channel = |v: f32| ((v + m) * 255f32).round() as u8;
+
+    usvg::Color::new_rgb(channel(r), channel(g), channel(b))
+}
+
+impl OwnershipSnapshot {
+    /// Creates a snapshot where every owner has an automatically assigned colour.
+    pub fn new(owners: impl Into<TerritoryMap<OwnerId>>) -> Self {
+        let mut snapshot = Self {
+            owners: owners.into(),
+            styles: BTreeMap::new(),
+        };
+        snapshot.assign_colours();
+        snapshot
+    }
+
+    /// All owners, sorted by ID.
+    pub fn owner_ids(&self) -> Vec<OwnerId> {
+        let owners: BTreeSet<_> = self.owners.values().copied().collect();
+        owners.into_iter().collect()
+    }
+
+    pub fn territories_of(&self, owner: OwnerId) -> TerritorySet {
+        self.owners
+            .iter()
+            .filter(|(_, o)| **o == owner)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Adds a style with the [`auto_colour`] for every owner that does not have one yet, so an
+    /// owner gets the same colour in every snapshot regardless of who else is on the map.
+    pub fn assign_colours(&mut self) {
+        for owner in self.owner_ids() {
+            self.styles.entry(owner).or_insert_with(|| OwnerStyle {
+                colour: auto_colour(owner),
+                label: None,
+            });
+        }
+    }
+
+    pub fn style(&self, owner: OwnerId) -> Option<&OwnerStyle> {
+        self.styles.get(&owner)
+    }
+
+    fn colour_of(&self, owner: OwnerId) -> usvg::Color {
+        self.style(owner)
+            .map_or_else(|| auto_colour(owner), |style| style.colour)
+    }
+
+    fn label_of(&self, owner: OwnerId) -> String {
+        self.style(owner)
+            .and_then(|style| style.label.clone())
+            .unwrap_or_else(|| owner.to_string())
+    }
+
+    /// Fill and stroke of every owned territory in the owner's colour.
+    pub fn instructions(
+        &self,
+    ) -> (
+        TerritoryMap<RenderInstruction>,
+        TerritoryMap<RenderInstruction>,
+    ) {
+        let mut fill = TerritoryMap::new();
+        let mut stroke = TerritoryMap::new();
+        for (id, owner) in self.owners.iter() {
+            let colour = self.colour_of(*owner);
+            fill.insert(
+                id,
+                RenderInstruction {
+                    colour,
+                    opacity: FILL_OPACITY,
+                },
+            );
+            stroke.insert(
+                id,
+                RenderInstruction {
+                    colour,
+                    opacity: STROKE_OPACITY,
+                },
+            );
+        }
+
+        (fill, stroke)
+    }
+}
+
+#[cfg(feature = "torn-api")]
+impl From<&crate::torn_api::TerritoryResponse> for OwnershipSnapshot {
+    fn from(value: &crate::torn_api::TerritoryResponse) -> Self {
+        Self::new(value.ownership())
+    }
+}
+
+/// How [`TerritoryDataset::render_ownership_diff`] highlights the changes.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub gained: usvg::Color,
+    pub lost: usvg::Color,
+    pub transferred: usvg::Color,
+    /// Fill opacity of unchanged holdings, which are drawn in the colour of their owner. Values
+    /// outside of `0.0..=1.0` are clamped.
+    pub dimmed_opacity: f32,
+    /// Lists the kinds of changes in the upper left corner.
+    pub legend: bool,
+    /// Fixed view port, by default the view port fits the changed territories.
+    pub view_port: Option<image::math::Rect>,
+    /// Padding of the fitted view port, see [`crate::bbox_for_territories`].
+    pub factor: f32,
+    pub aspect_ratio: f32,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            gained: colour_from_hex("#2ecc40").unwrap(),
+            lost: colour_from_hex("#ff4136").unwrap(),
+            transferred: colour_from_hex("#ff851b").unwrap(),
+            dimmed_opacity: 0.15,
+            legend: true,
+            view_port: None,
+            factor: 0.8,
+            aspect_ratio: 4f32 / 3f32,
+        }
+    }
+}
+
+/// Renders the territories in the colours of their owners, see
+/// [`TerritoryDataset::render_ownership`].
+#[cfg(feature = "embedded-tiles")]
+pub fn render_ownership(
+    snapshot: &OwnershipSnapshot,
+    view_port: image::math::Rect,
+    scale: RenderScale,
+    legend: bool,
+) -> RgbaImage {
+    TerritoryDataset::embedded().render_ownership(snapshot, view_port, scale, legend)
+}
+
+/// Highlights the changes between two snapshots, see [`TerritoryDataset::render_ownership_diff`].
+#[cfg(feature = "embedded-tiles")]
+pub fn render_ownership_diff(
+    before: &OwnershipSnapshot,
+    after: &OwnershipSnapshot,
+    scale: RenderScale,
+    options: &DiffOptions,
+) -> RgbaImage {
+    TerritoryDataset::embedded().render_ownership_diff(before, after, scale, options)
+}
+
+impl TerritoryDataset {
+    /// Renders the territories in the colours of their owners. The legend lists the owners with
+    /// the most territories in the view port in the upper left corner.
+    #[cfg(feature = "embedded-tiles")]
+    pub fn render_ownership(
+        &self,
+        snapshot: &OwnershipSnapshot,
+        view_port: image::math::Rect,
+        scale: RenderScale,
+        legend: bool,
+    ) -> RgbaImage {
+        // the embedded tiles are complete, see `TileSource`
+        self.render_ownership_with_tiles(&EmbeddedTiles, snapshot, view_port, scale, legend)
+            .unwrap()
+    }
+
+    /// Like [`TerritoryDataset::render_ownership`], but loads the map background from `tiles`.
+    pub fn render_ownership_with_tiles(
+        &self,
+        tiles: &(impl TileSource + ?Sized),
+        snapshot: &OwnershipSnapshot,
+        view_port: image::math::Rect,
+        scale: RenderScale,
+        legend: bool,
+    ) -> Result<RgbaImage, TileError> {
+        let background = render_background(tiles, view_port, scale)?;
+        Ok(self.render_ownership_over(&background, snapshot, view_port, scale, legend))
+    }
+
+    /// Renders the snapshot onto a copy of a background from [`render_background`], which lets
+    /// several snapshots of the same view port share the background.
+    pub(crate) fn render_ownership_over(
+        &self,
+        background: &RgbaImage,
+        snapshot: &OwnershipSnapshot,
+        view_port: image::math::Rect,
+        scale: RenderScale,
+        legend: bool,
+    ) -> RgbaImage {
+        let (fill, stroke) = snapshot.instructions();
+
+        let mut image = background.clone();
+        let shapes = self.render_shapes(view_port, fill, stroke, scale);
+        overlay(&mut image, &shapes, 0, 0);
+        if legend {
+            let entries = self.legend_entries(snapshot, view_port);
+            owner_legend(&mut image, snapshot, &entries);
+        }
+
+        image
+    }
+
+    /// Highlights the territories which were gained, lost or transferred between the snapshots.
+    /// Changed territories are filled in the colour of their kind of change and outlined in the
+    /// colour of their new owner, or of the previous owner if they were lost. Unchanged holdings
+    /// are dimmed.
+    #[cfg(feature = "embedded-tiles")]
+    pub fn render_ownership_diff(
+        &self,
+        before: &OwnershipSnapshot,
+        after: &OwnershipSnapshot,
+        scale: RenderScale,
+        options: &DiffOptions,
+    ) -> RgbaImage {
+        // the embedded tiles are complete, see `TileSource`
+        self.render_ownership_diff_with_tiles(&EmbeddedTiles, before, after, scale, options)
+            .unwrap()
+    }
+
+    /// Like [`TerritoryDataset::render_ownership_diff`], but loads the map background from
+    /// `tiles`.
+    pub fn render_ownership_diff_with_tiles(
+        &self,
+        tiles: &(impl TileSource + ?Sized),
+        before: &OwnershipSnapshot,
+        after: &OwnershipSnapshot,
+        scale: RenderScale,
+        options: &DiffOptions,
+    ) -> Result<RgbaImage, TileError> {
+        let diff = OwnershipDiff::new(&before.owners, &after.owners);
+        let view_port = options
+            .view_port
+            .unwrap_or_else(|| self.diff_view_port(&diff, options.factor, options.aspect_ratio));
+
+        // owners that only exist in one snapshot use that snapshot's colour
+        let colour = |owner| match after.styles.get(&owner).or(before.styles.get(&owner)) {
+            Some(style) => style.colour,
+            None => after.colour_of(owner),
+        };
+        let instruction = |colour, opacity| RenderInstruction { colour, opacity };
+        // `clamp` would keep NaN
+        let dimmed_opacity = if options.dimmed_opacity.is_nan() {
+            0f32
+        } else {
+            options.dimmed_opacity.clamp(0f32, 1f32)
+        };
+
+        let mut fill = TerritoryMap::new();
+        let mut stroke = TerritoryMap::new();
+        for (id, owner) in diff.unchanged.iter() {
+            fill.insert(id, instruction(colour(*owner), dimmed_opacity));
+        }
+        for (id, owner) in diff.gained.iter() {
+            fill.insert(id, instruction(options.gained, FILL_OPACITY));
+            stroke.insert(id, instruction(colour(*owner), STROKE_OPACITY));
+        }
+        for (id, owner) in diff.lost.iter() {
+            fill.insert(id, instruction(options.lost, FILL_OPACITY));
+            stroke.insert(id, instruction(colour(*owner), STROKE_OPACITY));
+        }
+        for (id, (_, owner)) in diff.transferred.iter() {
+            fill.insert(id, instruction(options.transferred, FILL_OPACITY));
+            stroke.insert(id, instruction(colour(*owner), STROKE_OPACITY));
+        }
+
+        let mut image =
+            self.render_territories_with_tiles(tiles, view_port, fill, stroke, scale)?;
+        if options.legend {
+            let rows: Vec<_> = [
+                (options.gained, "gained", diff.gained.len()),
+                (options.lost, "lost", diff.lost.len()),
+                (options.transferred, "transferred", diff.transferred.len()),
+            ]
+            .into_iter()
+            .filter(|(_, _, count)| *count > 0)
+            .map(|(colour, kind, count)| (Some(colour), format!("{kind} ({count})")))
+            .collect();
+            draw_legend(&mut image, &rows);
+        }
+
+        Ok(image)
+    }
+
+    /// A view port around all changed territories, or the whole map if nothing changed.
+    pub fn diff_view_port(
+        &self,
+        diff: &OwnershipDiff,
+        factor: f32,
+        aspect_ratio: f32,
+    ) -> image::math::Rect {
+        let changed: Vec<_> = diff.changed().iter().collect();
+        match self.bbox_for_territories(&changed, factor, aspect_ratio) {
+            Some(bbox) => fit_view_box(bbox),
+            None => MAP_BBOX,
+        }
+    }
+
+    /// Owners with territories in the view port, most territories first.
+    fn legend_entries(
+        &self,
+        snapshot: &OwnershipSnapshot,
+        view_port: image::math::Rect,
+    ) -> Vec<OwnerId> {
+        let mut counts = BTreeMap::new();
+        for (id, owner) in snapshot.owners.iter() {
+            let Some(bounds) = self.path_for_territory(id).map(|path| path.bounds()) else {
+                continue;
+            };
+            let visible = bounds.right() >= view_port.x as f32
+                && bounds.left() <= (view_port.x + view_port.width) as f32
+                && bounds.bottom() >= view_port.y as f32
+                && bounds.top() <= (view_port.y + view_port.height) as f32;
+            if visible {
+                *counts.entry(*owner).or_insert(0usize) += 1;
+            }
+        }
+
+        let mut entries: Vec<_> = counts.into_iter().collect();
+        entries.sort_by(|(a_owner, a_count), (b_owner, b_count)| {
+            b_count.cmp(a_count).then(a_owner.cmp(b_owner))
+        });
+        entries.into_iter().map(|(owner, _)| owner).collect()
+    }
+}
+
+fn owner_legend(image: &mut RgbaImage, snapshot: &OwnershipSnapshot, owners: &[OwnerId]) {
+    let mut rows: Vec<_> = owners
+        .iter()
+        .take(MAX_LEGEND_ENTRIES)
+        .map(|owner| (Some(snapshot.colour_of(*owner)), snapshot.label_of(*owner)))
+        .collect();
+    if owners.len() > MAX_LEGEND_ENTRIES {
+        rows.push((None, format!("+{} more", owners.len() - MAX_LEGEND_ENTRIES)));
+    }
+
+    draw_legend(image, &rows);
+}
+
+/// Draws rows of a colour swatch and a label in the upper left corner.
+fn draw_legend(image: &mut RgbaImage, rows: &[(Option<usvg::Color>, String)]) {
+    if rows.is_empty() {
+        return;
+    }
+
+    let (_, row_height) = text_size("", LEGEND_SCALE);
+    let swatch = row_height;
+    let text_width = rows
+        .iter()
+        .map(|(_, label)| text_size(label, LEGEND_SCALE).0)
+        .max()
+        .unwrap_or(0);
+    let width = LEGEND_PADDING * 3 + swatch + text_width;
+    let height = LEGEND_PADDING + rows.len() as u32 * (row_height + LEGEND_PADDING);
+
+    fill_rect(image, 0, 0, width, height, Rgba([255, 255, 255, 210]));
+    for (i, (colour, label)) in rows.iter().enumerate() {
+        let y = (LEGEND_PADDING + i as u32 * (row_height + LEGEND_PADDING)) as i64;
+        if let Some(colour) = colour {
+            let colour = Rgba([colour.red, colour.green, colour.blue, 255]);
+            fill_rect(image, LEGEND_PADDING as i64, y, swatch, swatch, colour);
+        }
+        let x = (LEGEND_PADDING * 2 + swatch) as i64;
+        draw_text(image, x, y, label, LEGEND_SCALE, Rgba([0, 0, 0, 255]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::territory;
+
+    fn snapshot() -> OwnershipSnapshot {
+        OwnershipSnapshot::new(TerritoryMap::from_iter([
+            (territory!("XOD"), 8151),
+            (territory!("UOD"), 8151),
+            (territory!("VOD"), 9356),
+        ]))
+    }
+
+    #[test]
+    fn test_auto_colours() {
+        let colours: Vec<_> = (8140..8164).map(auto_colour).collect();
+        for (i, a) in colours.iter().enumerate() {
+            for b in &colours[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+        assert_eq!(hsl_to_rgb(0f32, 1f32, 0.5), usvg::Color::new_rgb(255, 0, 0));
+        assert_eq!(
+            hsl_to_rgb(240f32, 1f32, 0.5),
+            usvg::Color::new_rgb(0, 0, 255)
+        );
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut snapshot = snapshot();
+        assert_eq!(snapshot.owner_ids(), vec![8151, 9356]);
+        assert_eq!(snapshot.territories_of(8151).len(), 2);
+        assert_eq!(snapshot.style(8151).unwrap().colour, auto_colour(8151));
+        assert_eq!(snapshot.style(9356).unwrap().colour, auto_colour(9356));
+
+        // explicit styles are kept when assigning colours
+        let red = usvg::Color::new_rgb(255, 0, 0);
+        snapshot.styles.insert(
+            9356,
+            OwnerStyle {
+                colour: red,
+                label: Some("Red".to_owned()),
+            },
+        );
+        snapshot.owners.insert(territory!("GVE"), 1);
+        snapshot.assign_colours();
+        assert_eq!(snapshot.style(9356).unwrap().colour, red);
+        // adding an owner does not change the colours of the others
+        assert_eq!(snapshot.style(1).unwrap().colour, auto_colour(1));
+        assert_eq!(snapshot.style(8151).unwrap().colour, auto_colour(8151));
+        assert_eq!(snapshot.label_of(9356), "Red");
+        assert_eq!(snapshot.label_of(1), "1");
+
+        let (fill, stroke) = snapshot.instructions();
+        assert_eq!(fill[territory!("VOD")].colour, red);
+        assert_eq!(stroke.len(), 4);
+
+        // owners without a style are drawn in their own colour, not in another owner's
+        snapshot.owners.insert(territory!("GVE"), 42);
+        let (fill, _) = snapshot.instructions();
+        assert_eq!(fill[territory!("GVE")].colour, auto_colour(42));
+    }
+
+    #[cfg(feature = "embedded-tiles")]
+    #[test]
+    fn test_render_ownership() {
+        let snapshot = snapshot();
+        let bounds = crate::path_for_territory(territory!("XOD"))
+            .unwrap()
+            .bounds();
+        let view_port = crate::fit_view_box(crate::bbox_for_path(
+            &crate::path_for_territory(territory!("XOD")).unwrap(),
+            0.5,
+            4f32 / 3f32,
+        ));
+
+        let plain = render_ownership(&snapshot, view_port, RenderScale::X1, false);
+        let with_legend = render_ownership(&snapshot, view_port, RenderScale::X1, true);
+        assert_eq!(plain.dimensions(), (view_port.width, view_port.height));
+
+        // the legend covers the upper left corner
+        assert_ne!(plain.get_pixel(2, 2), with_legend.get_pixel(2, 2));
+        let (x, y) = (view_port.width - 1, view_port.height - 1);
+        assert_eq!(plain.get_pixel(x, y), with_legend.get_pixel(x, y));
+
+        let mut unowned = snapshot.clone();
+        unowned.owners.clear();
+        let background = render_ownership(&unowned, view_port, RenderScale::X1, true);
+        let centre = (
+            (bounds.x() + bounds.width() / 2f32) as u32 - view_port.x,
+            (bounds.y() + bounds.height() / 2f32) as u32 - view_port.y,
+        );
+        assert_ne!(
+            plain.get_pixel(centre.0, centre.1),
+            background.get_pixel(centre.0, centre.1)
+        );
+        // without owners there is nothing to list
+        assert_eq!(background.get_pixel(2, 2), plain.get_pixel(2, 2));
+    }
+
+    #[cfg(feature = "embedded-tiles")]
+    #[test]
+    fn test_render_ownership_diff() {
+        let before = snapshot();
+        let mut after = before.clone();
+        after.owners.remove(territory!("UOD"));
+        after.owners.insert(territory!("VOD"), 8151);
+
+        let diff = OwnershipDiff::new(&before.owners, &after.owners);
+        let view_port = TerritoryDataset::embedded().diff_view_port(&diff, 0.8, 4f32 / 3f32);
+        for id in [territory!("UOD"), territory!("VOD")] {
+            let bounds = crate::path_for_territory(id).unwrap().bounds();
+            assert!(bounds.left() >= view_port.x as f32);
+            assert!(bounds.top() >= view_port.y as f32);
+            assert!(bounds.right() <= (view_port.x + view_port.width) as f32);
+            assert!(bounds.bottom() <= (view_port.y + view_port.height) as f32);
+        }
+
+        let options = DiffOptions::default();
+        let image = render_ownership_diff(&before, &after, RenderScale::X1, &options);
+        assert_eq!(image.dimensions(), (view_port.width, view_port.height));
+
+        // the changed territories are highlighted instead of drawn in the owner's colour
+        let plain = render_ownership(&after, view_port, RenderScale::X1, false);
+        let bounds = crate::path_for_territory(territory!("VOD"))
+            .unwrap()
+            .bounds();
+        let (x, y) = (
+            (bounds.x() + bounds.width() / 2f32) as u32 - view_port.x,
+            (bounds.y() + bounds.height() / 2f32) as u32 - view_port.y,
+        );
+        assert_ne!(image.get_pixel(x, y), plain.get_pixel(x, y));
+
+        let unchanged = DiffOptions {
+            view_port: Some(view_port),
+            legend: false,
+            ..Default::default()
+        };
+        let image = render_ownership_diff(&before, &before, RenderScale::X1, &unchanged);
+        let background = render_ownership(
+            &OwnershipSnapshot::default(),
+            view_port,
+            RenderScale::X1,
+            false,
+        );
+        assert_ne!(image.get_pixel(x, y), background.get_pixel(x, y));
+        assert_ne!(image.get_pixel(x, y), plain.get_pixel(x, y));
+
+        // out of range opacities are clamped instead of panicking
+        for dimmed_opacity in [2f32, -1f32, f32::NAN] {
+            let options = DiffOptions {
+                dimmed_opacity,
+                ..unchanged.clone()
+            };
+            render_ownership_diff(&before, &before, RenderScale::X1, &options);
+        }
+
+        let nothing = OwnershipDiff::default();
+        assert_eq!(
+            TerritoryDataset::embedded().diff_view_port(&nothing, 0.8, 1f32),
+            MAP_BBOX
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_snapshot_json() {
+        let snapshot = snapshot();
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["owners"]["XOD"], 8151);
+        assert_eq!(
+            json["styles"]["8151"]["colour"],
+            colour_hex(auto_colour(8151))
+        );
+
+        let parsed: OwnershipSnapshot = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.owners, snapshot.owners);
+        assert_eq!(parsed.styles, snapshot.styles);
+    }
+
+    #[cfg(feature = "json")]
+    fn colour_hex(colour: usvg::Color) -> String {
+        crate::colour_to_hex(colour)
+    }
+}
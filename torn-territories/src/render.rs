@@ -0,0 +1,393 @@
+use std::rc::Rc;
+
+use image::{
+    buffer::ConvertBuffer,
+    imageops::{crop, overlay},
+};
+use resvg::usvg::{self, NodeExt, Rect};
+
+#[cfg(feature = "serde")]
+use crate::serde_helpers;
+#[cfg(feature = "embedded-tiles")]
+use crate::EmbeddedTiles;
+use crate::{
+    load_map_segment_from, load_map_x4_from, TerritoryDataset, TerritoryId, TerritoryMap,
+    TileError, TileSource, MAP_HEIGHT, MAP_WIDTH,
+};
+
+pub const MAP_BBOX: image::math::Rect = image::math::Rect {
+    x: 0,
+    y: 0,
+    width: MAP_WIDTH,
+    height: MAP_HEIGHT,
+};
+
+pub fn colour_from_hex(hex: &str) -> Option<usvg::Color> {
+    let digits = hex.strip_prefix('#')?;
+    // `from_str_radix` would also accept a sign
+    if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let [_, r, g, b] = u32::from_str_radix(digits, 16).ok()?.to_be_bytes();
+
+    Some(usvg::Color::new_rgb(r, g, b))
+}
+
+pub fn colour_to_hex(colour: usvg::Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour.red, colour.green, colour.blue)
+}
+
+pub fn element_for_territory(
+    id: TerritoryId,
+    fill: Option<usvg::Fill>,
+    stroke: Option<usvg::Stroke>,
+) -> Option<usvg::Path> {
+    TerritoryDataset::embedded().element_for_territory(id, fill, stroke)
+}
+
+/// The whole map pixels covering the box. The view port is moved back onto the map if it reaches
+/// past an edge, and only shrunk if it is larger than the map.
+pub fn fit_view_box(bbox: Rect) -> image::math::Rect {
+    fn fit_axis(start: f32, end: f32, map_size: u32) -> (u32, u32) {
+        // round outwards, so the view port never cuts into the box
+        let start = start.floor() as i64;
+        let size = (end.ceil() as i64 - start).min(map_size as i64);
+        let start = start.clamp(0, map_size as i64 - size);
+        (start as u32, size as u32)
+    }
+
+    let (x, width) = fit_axis(bbox.left(), bbox.right(), MAP_WIDTH);
+    let (y, height) = fit_axis(bbox.top(), bbox.bottom(), MAP_HEIGHT);
+
+    image::math::Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderInstruction {
+    #[cfg_attr(feature = "serde", serde(with = "serde_helpers::colour"))]
+    pub colour: usvg::Color,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "serde_helpers::opacity"))]
+    pub opacity: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum RenderScale {
+    X1,
+    X4,
+}
+
+impl RenderScale {
+    /// Map pixels per image pixel.
+    pub(crate) fn factor(self) -> u32 {
+        match self {
+            Self::X1 => 1,
+            Self::X4 => 4,
+        }
+    }
+}
+
+#[cfg(feature = "embedded-tiles")]
+pub fn render_territories(
+    view_port: image::math::Rect,
+    fill: impl Into<TerritoryMap<RenderInstruction>>,
+    stroke: impl Into<TerritoryMap<RenderInstruction>>,
+    scale: RenderScale,
+) -> image::RgbaImage {
+    TerritoryDataset::embedded().render_territories(view_port, fill, stroke, scale)
+}
+
+impl TerritoryDataset {
+    pub fn element_for_territory(
+        &self,
+        id: TerritoryId,
+        fill: Option<usvg::Fill>,
+        stroke: Option<usvg::Stroke>,
+    ) -> Option<usvg::Path> {
+        Some(element_for_path(self.path_for_territory(id)?, fill, stroke))
+    }
+
+    /// Renders the territories onto the map. Territories which are not part of the dataset are
+    /// skipped.
+    #[cfg(feature = "embedded-tiles")]
+    pub fn render_territories(
+        &self,
+        view_port: image::math::Rect,
+        fill: impl Into<TerritoryMap<RenderInstruction>>,
+        stroke: impl Into<TerritoryMap<RenderInstruction>>,
+        scale: RenderScale,
+    ) -> image::RgbaImage {
+        // the embedded tiles are complete, see `TileSource`
+        self.render_territories_with_tiles(&EmbeddedTiles, view_port, fill, stroke, scale)
+            .unwrap()
+    }
+
+    /// Like [`TerritoryDataset::render_territories`], but loads the map background from `tiles`.
+    pub fn render_territories_with_tiles(
+        &self,
+        tiles: &(impl TileSource + ?Sized),
+        view_port: image::math::Rect,
+        fill: impl Into<TerritoryMap<RenderInstruction>>,
+        stroke: impl Into<TerritoryMap<RenderInstruction>>,
+        scale: RenderScale,
+    ) -> Result<image::RgbaImage, TileError> {
+        let mut background = render_background(tiles, view_port, scale)?;
+        let shapes = self.render_shapes(view_port, fill, stroke, scale);
+        overlay(&mut background, &shapes, 0, 0);
+
+        Ok(background)
+    }
+
+    /// The territories on a transparent background, to be laid over [`render_background`].
+    /// Territories which are not part of the dataset are skipped.
+    pub(crate) fn render_shapes(
+        &self,
+        view_port: image::math::Rect,
+        fill: impl Into<TerritoryMap<RenderInstruction>>,
+        stroke: impl Into<TerritoryMap<RenderInstruction>>,
+        scale: RenderScale,
+    ) -> image::RgbaImage {
+        let fill = fill.into();
+        let mut stroke = stroke.into();
+
+        let mut elements = Vec::with_capacity(fill.len() + stroke.len());
+        for (id, inst) in fill.iter() {
+            let border = stroke.remove(id).map(|i| stroke_for(&i));
+            elements.extend(self.element_for_territory(id, Some(fill_for(inst)), border));
+        }
+
+        for (id, inst) in stroke {
+            elements.extend(self.element_for_territory(id, None, Some(stroke_for(&inst))));
+        }
+
+        render_elements(elements, view_port, scale)
+    }
+}
+
+pub(crate) fn fill_for(inst: &RenderInstruction) -> usvg::Fill {
+    usvg::Fill {
+        paint: usvg::Paint::Color(inst.colour),
+        opacity: usvg::NormalizedF32::new(inst.opacity).unwrap(),
+        rule: usvg::FillRule::NonZero,
+    }
+}
+
+pub(crate) fn stroke_for(inst: &RenderInstruction) -> usvg::Stroke {
+    usvg::Stroke {
+        paint: usvg::Paint::Color(inst.colour),
+        dasharray: None,
+        dashoffset: 0f32,
+        miterlimit: usvg::StrokeMiterlimit::new(4f32),
+        opacity: usvg::NormalizedF32::new(inst.opacity).unwrap(),
+        width: usvg::NonZeroPositiveF32::new(4f32).unwrap(),
+        linecap: usvg::LineCap::Butt,
+        linejoin: usvg::LineJoin::Miter,
+    }
+}
+
+pub(crate) fn element_for_path(
+    data: tiny_skia_path::Path,
+    fill: Option<usvg::Fill>,
+    stroke: Option<usvg::Stroke>,
+) -> usvg::Path {
+    usvg::Path {
+        id: "".to_owned(),
+        transform: usvg::Transform::identity(),
+        visibility: usvg::Visibility::Visible,
+        fill,
+        stroke,
+        paint_order: usvg::PaintOrder::FillAndStroke,
+        rendering_mode: usvg::ShapeRendering::CrispEdges,
+        text_bbox: None,
+        data: Rc::new(data),
+    }
+}
+
+/// The elements on a transparent background, to be laid over [`render_background`].
+pub(crate) fn render_elements(
+    elements: impl IntoIterator<Item = usvg::Path>,
+    view_port: image::math::Rect,
+    scale: RenderScale,
+) -> image::RgbaImage {
+    let root = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+        id: "".to_owned(),
+        transform: usvg::Transform::identity(),
+        opacity: usvg::NormalizedF32::ONE,
+        blend_mode: usvg::BlendMode::Normal,
+        isolate: false,
+        clip_path: None,
+        mask: None,
+        filters: vec![],
+    }));
+
+    for element in elements {
+        root.append_kind(usvg::NodeKind::Path(element));
+    }
+
+    let scaled_width = view_port.width / scale.factor();
+    let scaled_height = view_port.height / scale.factor();
+
+    let tree = resvg::Tree::from_usvg(&usvg::Tree {
+        size: usvg::Size::from_wh(scaled_width as f32, scaled_height as f32).unwrap(),
+        view_box: usvg::ViewBox {
+            rect: usvg::NonZeroRect::from_xywh(
+                view_port.x as f32,
+                view_port.y as f32,
+                view_port.width as f32,
+                view_port.height as f32,
+            )
+            .unwrap(),
+            aspect: usvg::AspectRatio::default(),
+        },
+        root,
+    });
+
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(scaled_width, scaled_height).unwrap();
+    tree.render(
+        resvg::tiny_skia::Transform::identity(),
+        &mut pixmap.as_mut(),
+    );
+
+    image::RgbaImage::from_raw(scaled_width, scaled_height, pixmap.take()).unwrap()
+}
+
+/// The map background of the view port at the given scale.
+pub(crate) fn render_background(
+    tiles: &(impl TileSource + ?Sized),
+    view_port: image::math::Rect,
+    scale: RenderScale,
+) -> Result<image::RgbaImage, TileError> {
+    let scaled_width = view_port.width / scale.factor();
+    let scaled_height = view_port.height / scale.factor();
+
+    let background = match scale {
+        RenderScale::X1 => load_map_segment_from(
+            tiles,
+            view_port.x,
+            view_port.y,
+            view_port.width,
+            view_port.height,
+        )?
+        .convert(),
+        RenderScale::X4 => crop(
+            &mut load_map_x4_from(tiles)?,
+            view_port.x / 4,
+            view_port.y / 4,
+            scaled_width,
+            scaled_height,
+        )
+        .to_image()
+        .convert(),
+    };
+
+    Ok(background)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colour_hex() {
+        let colour = colour_from_hex("#ff8000").unwrap();
+        assert_eq!(colour, usvg::Color::new_rgb(255, 128, 0));
+        assert_eq!(colour_to_hex(colour), "#ff8000");
+
+        assert!(colour_from_hex("ff8000").is_none());
+        assert!(colour_from_hex("#gg0000").is_none());
+        assert!(colour_from_hex("#+ff000").is_none());
+        assert!(colour_from_hex("#aééb").is_none());
+        assert!(colour_from_hex("#ff80001").is_none());
+    }
+
+    #[test]
+    fn test_fit_view_box() {
+        let view_port = fit_view_box(Rect::from_xywh(10.5, 20.25, 100.2, 50f32).unwrap());
+        assert_eq!(
+            view_port,
+            image::math::Rect {
+                x: 10,
+                y: 20,
+                width: 101,
+                height: 51
+            }
+        );
+
+        let past_edge = fit_view_box(Rect::from_xywh(-10.5, 3_600f32, 100f32, 100f32).unwrap());
+        assert_eq!((past_edge.x, past_edge.width), (0, 101));
+        assert_eq!(past_edge.y + past_edge.height, MAP_HEIGHT);
+
+        let larger = fit_view_box(Rect::from_xywh(-100f32, -100f32, 7_000f32, 4_000f32).unwrap());
+        assert_eq!(larger, MAP_BBOX);
+    }
+
+    #[cfg(feature = "embedded-tiles")]
+    #[test]
+    fn test_render_unknown_territory() {
+        let gve: TerritoryId = "GVE".parse().unwrap();
+        let dataset: TerritoryDataset = TerritoryDataset::embedded()
+            .iter()
+            .filter(|(id, _)| *id != gve)
+            .map(|(id, info)| (id, info.clone()))
+            .collect();
+        let inst = RenderInstruction {
+            colour: colour_from_hex("#ff0000").unwrap(),
+            opacity: 1f32,
+        };
+        let view_port = fit_view_box(crate::bbox_for_path(
+            &crate::path_for_territory(gve).unwrap(),
+            0.8,
+            4f32 / 3f32,
+        ));
+
+        // territories missing from the dataset are skipped instead of panicking
+        let fill = TerritoryMap::from_iter([(gve, inst.clone())]);
+        let stroke = TerritoryMap::from_iter([(gve, inst)]);
+        let image = dataset.render_territories(view_port, fill, stroke, RenderScale::X1);
+        let plain = dataset.render_territories(
+            view_port,
+            TerritoryMap::new(),
+            TerritoryMap::new(),
+            RenderScale::X1,
+        );
+        assert_eq!(image, plain);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_render_json() {
+        let inst = RenderInstruction {
+            colour: colour_from_hex("#ff8000").unwrap(),
+            opacity: 0.5,
+        };
+        let value = serde_json::to_value(&inst).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "colour": "#ff8000", "opacity": 0.5 })
+        );
+
+        let parsed: RenderInstruction = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.colour, inst.colour);
+
+        for invalid in [
+            serde_json::json!({ "colour": "#aééb", "opacity": 0.5 }),
+            serde_json::json!({ "colour": "#ff8000", "opacity": 1.5 }),
+            serde_json::json!({ "colour": "#ff8000", "opacity": -0.1 }),
+        ] {
+            assert!(serde_json::from_value::<RenderInstruction>(invalid).is_err());
+        }
+
+        assert_eq!(serde_json::to_value(RenderScale::X4).unwrap(), "x4");
+    }
+}
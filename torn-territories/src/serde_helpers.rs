@@ -0,0 +1,66 @@
+//! `#[serde(with = "...")]` helpers for foreign types.
+
+pub mod shape {
+    use std::borrow::Cow;
+
+    use crate::{shape, PathSegment};
+
+    pub fn serialize<S>(shape: &[PathSegment], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&shape::svg_path(shape))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Cow<'static, [PathSegment]>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let path = <Cow<'de, str> as serde::Deserialize>::deserialize(deserializer)?;
+        shape::parse_svg_path(&path)
+            .map(Cow::Owned)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "render")]
+pub mod colour {
+    use resvg::usvg;
+
+    pub fn serialize<S>(colour: &usvg::Color, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&crate::colour_to_hex(*colour))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<usvg::Color, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let hex = <std::borrow::Cow<'de, str> as serde::Deserialize>::deserialize(deserializer)?;
+        crate::colour_from_hex(&hex).ok_or_else(|| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&hex),
+                &"a colour in the form #rrggbb",
+            )
+        })
+    }
+}
+
+/// Deserialises an opacity and rejects values outside of `0.0..=1.0`.
+#[cfg(feature = "render")]
+pub fn opacity<'de, D>(deserializer: D) -> Result<f32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opacity = <f32 as serde::Deserialize>::deserialize(deserializer)?;
+    if (0f32..=1f32).contains(&opacity) {
+        Ok(opacity)
+    } else {
+        Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Float(opacity.into()),
+            &"an opacity between 0.0 and 1.0",
+        ))
+    }
+}
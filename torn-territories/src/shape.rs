@@ -0,0 +1,145 @@
+use alloc::string::String;
+
+/// A segment of a territory outline in absolute map coordinates.
+///
+/// Mirrors `svgtypes::SimplePathSegment`, which is not available without `std`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    MoveTo {
+        x: f64,
+        y: f64,
+    },
+    LineTo {
+        x: f64,
+        y: f64,
+    },
+    Quadratic {
+        x1: f64,
+        y1: f64,
+        x: f64,
+        y: f64,
+    },
+    CurveTo {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    ClosePath,
+}
+
+#[cfg(feature = "std")]
+impl From<svgtypes::SimplePathSegment> for PathSegment {
+    fn from(value: svgtypes::SimplePathSegment) -> Self {
+        use svgtypes::SimplePathSegment;
+
+        match value {
+            SimplePathSegment::MoveTo { x, y } => Self::MoveTo { x, y },
+            SimplePathSegment::LineTo { x, y } => Self::LineTo { x, y },
+            SimplePathSegment::Quadratic { x1, y1, x, y } => Self::Quadratic { x1, y1, x, y },
+            SimplePathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => Self::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            },
+            SimplePathSegment::ClosePath => Self::ClosePath,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<PathSegment> for svgtypes::SimplePathSegment {
+    fn from(value: PathSegment) -> Self {
+        match value {
+            PathSegment::MoveTo { x, y } => Self::MoveTo { x, y },
+            PathSegment::LineTo { x, y } => Self::LineTo { x, y },
+            PathSegment::Quadratic { x1, y1, x, y } => Self::Quadratic { x1, y1, x, y },
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => Self::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            },
+            PathSegment::ClosePath => Self::ClosePath,
+        }
+    }
+}
+
+pub(crate) fn svg_path(shape: &[PathSegment]) -> String {
+    use core::fmt::Write;
+
+    let mut path = String::new();
+    for segment in shape {
+        match segment {
+            PathSegment::MoveTo { x, y } => write!(path, "M{x},{y}"),
+            PathSegment::LineTo { x, y } => write!(path, "L{x},{y}"),
+            PathSegment::Quadratic { x1, y1, x, y } => write!(path, "Q{x1},{y1},{x},{y}"),
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => write!(path, "C{x1},{y1},{x2},{y2},{x},{y}"),
+            PathSegment::ClosePath => write!(path, "Z"),
+        }
+        .unwrap();
+    }
+
+    path
+}
+
+/// Parses an SVG path string into absolute segments.
+#[cfg(feature = "serde")]
+pub(crate) fn parse_svg_path(path: &str) -> Result<alloc::vec::Vec<PathSegment>, svgtypes::Error> {
+    svgtypes::SimplifyingPathParser::from(path)
+        .map(|segment| segment.map(PathSegment::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_svgtypes_roundtrip() {
+        let shape = &crate::territory!("XOD").info().shape;
+        for segment in shape.iter() {
+            let converted: svgtypes::SimplePathSegment = (*segment).into();
+            assert_eq!(PathSegment::from(converted), *segment);
+        }
+    }
+
+    #[test]
+    fn test_svg_path() {
+        let shape = [
+            PathSegment::MoveTo { x: 1f64, y: 2f64 },
+            PathSegment::LineTo { x: 3.5, y: 2f64 },
+            PathSegment::ClosePath,
+        ];
+        assert_eq!(svg_path(&shape), "M1,2L3.5,2Z");
+    }
+}
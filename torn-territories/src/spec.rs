@@ -0,0 +1,401 @@
+use std::io::Cursor;
+
+use image::ImageFormat;
+
+#[cfg(feature = "embedded-tiles")]
+use crate::EmbeddedTiles;
+use crate::{
+    fit_view_box, geometry::checked_bbox_for_path, RenderInstruction, RenderScale,
+    TerritoryDataset, TerritoryId, TerritoryMap, TileError, TileSource, MAP_BBOX, MAP_HEIGHT,
+    MAP_WIDTH,
+};
+
+/// A complete description of a map render, which can be stored and replayed later.
+///
+/// ```
+/// # #[cfg(all(feature = "json", feature = "embedded-tiles"))]
+/// # {
+/// let spec: torn_territories::RenderSpec = serde_json::from_str(r##"{
+///     "viewport": { "type": "territory", "id": "XOD", "factor": 0.8 },
+///     "fill": [{ "colour": "#ff0000", "opacity": 0.5, "territories": ["XOD"] }]
+/// }"##).unwrap();
+/// let png = torn_territories::render_spec(&spec).unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderSpec {
+    pub viewport: Viewport,
+    /// Defaults to [`RenderScale::X4`] for the whole map and to [`RenderScale::X1`] otherwise.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scale: Option<RenderScale>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fill: Vec<RenderLayer>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stroke: Vec<RenderLayer>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum Viewport {
+    /// An explicit rectangle in map pixels.
+    Rect {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// Frames a single territory, see [`crate::bbox_for_path`].
+    Territory {
+        id: TerritoryId,
+        #[cfg_attr(feature = "serde", serde(default = "default_factor"))]
+        factor: f32,
+        #[cfg_attr(feature = "serde", serde(default = "default_aspect_ratio"))]
+        aspect_ratio: f32,
+    },
+    WholeMap,
+}
+
+#[cfg(feature = "serde")]
+fn default_factor() -> f32 {
+    1f32
+}
+
+#[cfg(feature = "serde")]
+fn default_aspect_ratio() -> f32 {
+    4f32 / 3f32
+}
+
+/// The same instruction applied to several territories.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderLayer {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub instruction: RenderInstruction,
+    pub territories: Vec<TerritoryId>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Tiff,
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    UnknownTerritory(TerritoryId),
+    InvalidViewport,
+    InvalidOpacity(f32),
+    Image(image::ImageError),
+    Tiles(TileError),
+    /// An animation needs at least one frame.
+    EmptyAnimation,
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnknownTerritory(id) => write!(f, "territory {id} is not part of the dataset"),
+            Self::InvalidViewport => write!(f, "viewport is empty or outside of the map"),
+            Self::InvalidOpacity(opacity) => {
+                write!(f, "opacity {opacity} is not between 0.0 and 1.0")
+            }
+            Self::Image(why) => write!(f, "failed to encode image: {why}"),
+            Self::Tiles(why) => write!(f, "failed to load map background: {why}"),
+            Self::EmptyAnimation => write!(f, "animation has no frames"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Image(why) => Some(why),
+            Self::Tiles(why) => Some(why),
+            _ => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for RenderError {
+    fn from(value: image::ImageError) -> Self {
+        Self::Image(value)
+    }
+}
+
+impl From<TileError> for RenderError {
+    fn from(value: TileError) -> Self {
+        Self::Tiles(value)
+    }
+}
+
+/// Renders the spec with the embedded dataset and returns the encoded image.
+#[cfg(feature = "embedded-tiles")]
+pub fn render_spec(spec: &RenderSpec) -> Result<Vec<u8>, RenderError> {
+    TerritoryDataset::embedded().render_spec(spec)
+}
+
+impl TerritoryDataset {
+    /// Renders the spec and returns the encoded image.
+    #[cfg(feature = "embedded-tiles")]
+    pub fn render_spec(&self, spec: &RenderSpec) -> Result<Vec<u8>, RenderError> {
+        self.render_spec_with_tiles(&EmbeddedTiles, spec)
+    }
+
+    /// Like [`TerritoryDataset::render_spec`], but loads the map background from `tiles`.
+    pub fn render_spec_with_tiles(
+        &self,
+        tiles: &(impl TileSource + ?Sized),
+        spec: &RenderSpec,
+    ) -> Result<Vec<u8>, RenderError> {
+        let image = self.render_spec_image_with_tiles(tiles, spec)?;
+
+        let mut buf = Vec::new();
+        let format = match spec.format {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Tiff => ImageFormat::Tiff,
+        };
+        image::DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut buf), format)?;
+
+        Ok(buf)
+    }
+
+    /// Renders the spec without encoding it, ignoring [`RenderSpec::format`].
+    #[cfg(feature = "embedded-tiles")]
+    pub fn render_spec_image(&self, spec: &RenderSpec) -> Result<image::RgbaImage, RenderError> {
+        self.render_spec_image_with_tiles(&EmbeddedTiles, spec)
+    }
+
+    /// Like [`TerritoryDataset::render_spec_image`], but loads the map background from `tiles`.
+    pub fn render_spec_image_with_tiles(
+        &self,
+        tiles: &(impl TileSource + ?Sized),
+        spec: &RenderSpec,
+    ) -> Result<image::RgbaImage, RenderError> {
+        let view_port = match spec.viewport {
+            Viewport::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                if width == 0
+                    || height == 0
+                    || x.saturating_add(width) > MAP_WIDTH
+                    || y.saturating_add(height) > MAP_HEIGHT
+                {
+                    return Err(RenderError::InvalidViewport);
+                }
+                image::math::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                }
+            }
+            Viewport::Territory {
+                id,
+                factor,
+                aspect_ratio,
+            } => {
+                let path = self
+                    .path_for_territory(id)
+                    .ok_or(RenderError::UnknownTerritory(id))?;
+                // extreme factors or aspect ratios leave nothing to render
+                checked_bbox_for_path(&path, factor, aspect_ratio)
+                    .map(fit_view_box)
+                    .filter(|view_port| view_port.width > 0 && view_port.height > 0)
+                    .ok_or(RenderError::InvalidViewport)?
+            }
+            Viewport::WholeMap => MAP_BBOX,
+        };
+
+        let scale = spec.scale.unwrap_or(match spec.viewport {
+            Viewport::WholeMap => RenderScale::X4,
+            _ => RenderScale::X1,
+        });
+        if matches!(scale, RenderScale::X4) && (view_port.width < 4 || view_port.height < 4) {
+            return Err(RenderError::InvalidViewport);
+        }
+
+        let fill = self.layers(&spec.fill)?;
+        let stroke = self.layers(&spec.stroke)?;
+
+        Ok(self.render_territories_with_tiles(tiles, view_port, fill, stroke, scale)?)
+    }
+
+    /// Later layers take precedence over earlier ones.
+    fn layers(
+        &self,
+        layers: &[RenderLayer],
+    ) -> Result<TerritoryMap<RenderInstruction>, RenderError> {
+        let mut map = TerritoryMap::new();
+        for layer in layers {
+            let opacity = layer.instruction.opacity;
+            if !(0f32..=1f32).contains(&opacity) {
+                return Err(RenderError::InvalidOpacity(opacity));
+            }
+
+            for id in &layer.territories {
+                if !self.contains(*id) {
+                    return Err(RenderError::UnknownTerritory(*id));
+                }
+                map.insert(*id, layer.instruction.clone());
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{colour_from_hex, territory};
+
+    fn spec() -> RenderSpec {
+        RenderSpec {
+            viewport: Viewport::Rect {
+                x: 100,
+                y: 100,
+                width: 64,
+                height: 48,
+            },
+            scale: None,
+            fill: vec![RenderLayer {
+                instruction: RenderInstruction {
+                    colour: colour_from_hex("#ff0000").unwrap(),
+                    opacity: 0.5,
+                },
+                territories: vec![territory!("XOD")],
+            }],
+            stroke: vec![],
+            format: OutputFormat::Png,
+        }
+    }
+
+    #[cfg(feature = "embedded-tiles")]
+    #[test]
+    fn test_render_spec() {
+        let png = render_spec(&spec()).unwrap();
+        let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 48));
+
+        let mut invalid = spec();
+        invalid.fill[0].instruction.opacity = 2f32;
+        assert!(matches!(
+            render_spec(&invalid),
+            Err(RenderError::InvalidOpacity(_))
+        ));
+
+        invalid.viewport = Viewport::Rect {
+            x: MAP_WIDTH - 10,
+            y: 0,
+            width: 20,
+            height: 20,
+        };
+        assert!(matches!(
+            render_spec(&invalid),
+            Err(RenderError::InvalidViewport)
+        ));
+
+        // nothing is left after scaling down
+        let mut tiny = spec();
+        tiny.viewport = Viewport::Rect {
+            x: 0,
+            y: 0,
+            width: 3,
+            height: 3,
+        };
+        tiny.scale = Some(RenderScale::X4);
+        assert!(matches!(
+            render_spec(&tiny),
+            Err(RenderError::InvalidViewport)
+        ));
+
+        let extreme = |factor, aspect_ratio| {
+            let mut extreme = spec();
+            extreme.viewport = Viewport::Territory {
+                id: territory!("XOD"),
+                factor,
+                aspect_ratio,
+            };
+            render_spec(&extreme)
+        };
+
+        // the fitted view port would not be finite
+        for (factor, aspect_ratio) in [(1f32, 1e-38), (1e-38, 4f32 / 3f32)] {
+            assert!(matches!(
+                extreme(factor, aspect_ratio),
+                Err(RenderError::InvalidViewport)
+            ));
+        }
+
+        // the view port is rounded up to at least a single pixel
+        let png = extreme(1e9, 4f32 / 3f32).unwrap();
+        let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert!(image.width() <= 2 && image.height() <= 2);
+    }
+
+    #[test]
+    fn test_render_spec_with_tiles() {
+        let dataset = TerritoryDataset::embedded();
+        let mut tiles = crate::MemoryTiles::new();
+        assert!(matches!(
+            dataset.render_spec_with_tiles(&tiles, &spec()),
+            Err(RenderError::Tiles(TileError::Missing(_)))
+        ));
+
+        for name in crate::tile_names(100, 100, 64, 48) {
+            let path = format!("{}/static/map_tiles/{name}", env!("CARGO_MANIFEST_DIR"));
+            tiles.insert(name, std::fs::read(path).unwrap());
+        }
+        let png = dataset.render_spec_with_tiles(&tiles, &spec()).unwrap();
+        let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 48));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_spec_toml() {
+        let spec: RenderSpec = toml::from_str(
+            r##"
+            format = "tiff"
+
+            [viewport]
+            type = "territory"
+            id = "XOD"
+            factor = 0.5
+
+            [[fill]]
+            colour = "#00ff00"
+            opacity = 0.25
+            territories = ["XOD", "GVE"]
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(spec.format, OutputFormat::Tiff);
+        assert!(matches!(
+            spec.viewport,
+            Viewport::Territory { factor, aspect_ratio, .. } if factor == 0.5 && aspect_ratio == 4f32 / 3f32
+        ));
+        assert_eq!(spec.fill[0].territories.len(), 2);
+
+        let json = serde_json::to_string(&spec).unwrap();
+        let spec: RenderSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(spec.fill[0].instruction.opacity, 0.25);
+    }
+}
@@ -0,0 +1,242 @@
+use std::{borrow::Cow, collections::HashMap, io::Cursor, ops::Deref};
+
+use image::{
+    codecs::tiff::TiffDecoder, imageops::replace, ColorType, GenericImageView, GrayImage,
+    ImageDecoder,
+};
+
+const TILE_WIDTH: u32 = 600;
+const TILE_HEIGHT: u32 = 400;
+pub(crate) const X4_TILE: &str = "map_x4.tiff";
+
+/// Provides the greyscale TIFF images that make up the map background.
+///
+/// The full resolution map is split into 600x400 tiles named `map_{x}_{y}.tiff`, where `x` and
+/// `y` start at 1 in the upper left corner. `map_x4.tiff` contains the whole map at a quarter of
+/// the resolution.
+pub trait TileSource {
+    fn tile(&self, name: &str) -> Option<Cow<'_, [u8]>>;
+}
+
+/// The map tiles which are embedded into the binary.
+#[cfg(feature = "embedded-tiles")]
+#[derive(rust_embed::RustEmbed)]
+#[folder = "static/map_tiles"]
+#[include = "*.tiff"]
+pub struct EmbeddedTiles;
+
+#[cfg(feature = "embedded-tiles")]
+impl TileSource for EmbeddedTiles {
+    fn tile(&self, name: &str) -> Option<Cow<'_, [u8]>> {
+        Self::get(name).map(|file| file.data)
+    }
+}
+
+/// Tiles which were loaded at runtime, e.g. fetched over the network.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTiles(HashMap<String, Vec<u8>>);
+
+impl MemoryTiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, data: Vec<u8>) {
+        self.0.insert(name.into(), data);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+}
+
+impl TileSource for MemoryTiles {
+    fn tile(&self, name: &str) -> Option<Cow<'_, [u8]>> {
+        self.0.get(name).map(|data| Cow::Borrowed(data.as_slice()))
+    }
+}
+
+impl<T: TileSource + ?Sized> TileSource for &T {
+    fn tile(&self, name: &str) -> Option<Cow<'_, [u8]>> {
+        (**self).tile(name)
+    }
+}
+
+#[derive(Debug)]
+pub enum TileError {
+    Missing(String),
+    Decode(String, image::ImageError),
+    /// The tile is not an 8 bit greyscale image, or smaller than the part of the map it covers.
+    InvalidFormat(String),
+}
+
+impl std::fmt::Display for TileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "map tile '{name}' is not available"),
+            Self::Decode(name, why) => write!(f, "failed to decode map tile '{name}': {why}"),
+            Self::InvalidFormat(name) => {
+                write!(
+                    f,
+                    "map tile '{name}' is not an 8 bit greyscale image of the right size"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(_, why) => Some(why),
+            _ => None,
+        }
+    }
+}
+
+/// Names of the tiles that are needed to load the given part of the map at full resolution.
+pub fn tile_names(x: u32, y: u32, w: u32, h: u32) -> Vec<String> {
+    if w == 0 || h == 0 {
+        return vec![];
+    }
+
+    let columns = (x / TILE_WIDTH + 1)..=((x + w - 1) / TILE_WIDTH + 1);
+    let rows = (y / TILE_HEIGHT + 1)..=((y + h - 1) / TILE_HEIGHT + 1);
+
+    rows.flat_map(|y_tile| {
+        columns
+            .clone()
+            .map(move |x_tile| format!("map_{x_tile}_{y_tile}.tiff"))
+    })
+    .collect()
+}
+
+fn decode_tile(tiles: &(impl TileSource + ?Sized), name: &str) -> Result<GrayImage, TileError> {
+    let data = tiles
+        .tile(name)
+        .ok_or_else(|| TileError::Missing(name.to_owned()))?;
+
+    let decoder = TiffDecoder::new(Cursor::new(data))
+        .map_err(|why| TileError::Decode(name.to_owned(), why))?;
+    if decoder.color_type() != ColorType::L8 {
+        return Err(TileError::InvalidFormat(name.to_owned()));
+    }
+    let mut buf = vec![0; decoder.total_bytes() as usize];
+    let (d_x, d_y) = decoder.dimensions();
+    decoder
+        .read_image(&mut buf)
+        .map_err(|why| TileError::Decode(name.to_owned(), why))?;
+
+    GrayImage::from_raw(d_x, d_y, buf).ok_or_else(|| TileError::InvalidFormat(name.to_owned()))
+}
+
+pub fn load_map_segment_from(
+    tiles: &(impl TileSource + ?Sized),
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+) -> Result<GrayImage, TileError> {
+    let mut image = GrayImage::new(w, h);
+    let mut cursor = (x, y);
+
+    while cursor.1 < (y + h) {
+        let x_tile = cursor.0 / TILE_WIDTH + 1;
+        let y_tile = cursor.1 / TILE_HEIGHT + 1;
+        let x_min = cursor.0 % TILE_WIDTH;
+        let y_min = cursor.1 % TILE_HEIGHT;
+        let width = ((x + w) - cursor.0).min(TILE_WIDTH - (cursor.0 % TILE_WIDTH));
+        let height = ((y + h) - cursor.1).min(TILE_HEIGHT - (cursor.1 % TILE_HEIGHT));
+
+        let name = format!("map_{x_tile}_{y_tile}.tiff");
+        let tile = decode_tile(tiles, &name)?;
+        // tiles from other sources than the embedded ones may be truncated
+        if tile.width() < x_min + width || tile.height() < y_min + height {
+            return Err(TileError::InvalidFormat(name));
+        }
+        let view = tile.view(x_min, y_min, width, height);
+        replace(
+            &mut image,
+            view.deref(),
+            (cursor.0 - x) as i64,
+            (cursor.1 - y) as i64,
+        );
+
+        if cursor.0 + width >= x + w {
+            cursor = (x, cursor.1 + height);
+        } else {
+            cursor.0 += width;
+        }
+    }
+
+    Ok(image)
+}
+
+pub fn load_map_x4_from(tiles: &(impl TileSource + ?Sized)) -> Result<GrayImage, TileError> {
+    decode_tile(tiles, X4_TILE)
+}
+
+#[cfg(feature = "embedded-tiles")]
+pub fn load_map_segment(x: u32, y: u32, w: u32, h: u32) -> GrayImage {
+    load_map_segment_from(&EmbeddedTiles, x, y, w, h).unwrap()
+}
+
+#[cfg(feature = "embedded-tiles")]
+pub fn load_map_x4() -> GrayImage {
+    load_map_x4_from(&EmbeddedTiles).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_names() {
+        assert_eq!(tile_names(0, 0, 600, 400), vec!["map_1_1.tiff"]);
+        assert_eq!(
+            tile_names(599, 399, 2, 2),
+            vec![
+                "map_1_1.tiff",
+                "map_2_1.tiff",
+                "map_1_2.tiff",
+                "map_2_2.tiff"
+            ]
+        );
+        assert!(tile_names(10, 10, 0, 5).is_empty());
+    }
+
+    #[cfg(feature = "embedded-tiles")]
+    #[test]
+    fn test_memory_tiles() {
+        let mut tiles = MemoryTiles::new();
+        assert!(matches!(
+            load_map_segment_from(&tiles, 590, 10, 20, 20),
+            Err(TileError::Missing(_))
+        ));
+
+        for name in tile_names(590, 10, 20, 20) {
+            let data = EmbeddedTiles.tile(&name).unwrap().into_owned();
+            tiles.insert(name, data);
+        }
+
+        let from_memory = load_map_segment_from(&tiles, 590, 10, 20, 20).unwrap();
+        assert_eq!(from_memory, load_map_segment(590, 10, 20, 20));
+    }
+
+    #[test]
+    fn test_undersized_tile() {
+        let mut data = Vec::new();
+        image::codecs::tiff::TiffEncoder::new(Cursor::new(&mut data))
+            .encode(&[0; 100 * 100], 100, 100, ColorType::L8)
+            .unwrap();
+
+        let mut tiles = MemoryTiles::new();
+        tiles.insert("map_1_1.tiff", data);
+
+        assert!(load_map_segment_from(&tiles, 10, 10, 50, 50).is_ok());
+        assert!(matches!(
+            load_map_segment_from(&tiles, 90, 10, 50, 50),
+            Err(TileError::InvalidFormat(name)) if name == "map_1_1.tiff"
+        ));
+    }
+}
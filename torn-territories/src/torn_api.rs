@@ -0,0 +1,292 @@
+//! Models for the territory selections of the Torn API, e.g. the responses of
+//! `torn/?selections=territory`, `torn/?selections=rackets` and `torn/?selections=territorywars`.
+//! The `faction/` endpoints use the same schemas.
+//!
+//! Responses with several selections can be deserialised into each of the response types, as
+//! unknown fields are ignored. Territories which are not part of the embedded dataset fail to
+//! deserialise.
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::Timestamp;
+use crate::{OwnerId, TerritoryMap, TerritorySet};
+
+/// The ID of a faction in Torn.
+pub type FactionId = OwnerId;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Territory {
+    pub sector: u8,
+    #[serde(default)]
+    pub size: u32,
+    #[serde(default)]
+    pub density: u32,
+    #[serde(default)]
+    pub slots: u16,
+    #[serde(default)]
+    pub daily_respect: u32,
+    /// The owning faction, the API uses `0` for unclaimed territories.
+    #[serde(default, with = "faction")]
+    pub faction: Option<FactionId>,
+    #[serde(default)]
+    pub coordinate_x: f64,
+    #[serde(default)]
+    pub coordinate_y: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub racket: Option<Racket>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Racket {
+    pub name: String,
+    pub level: u8,
+    pub reward: String,
+    pub created: Timestamp,
+    pub changed: Timestamp,
+    /// Only part of the `rackets` selection, where it is the faction owning the territory.
+    #[serde(default, with = "faction")]
+    pub faction: Option<FactionId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerritoryWar {
+    pub territory_war_id: u64,
+    pub assaulting_faction: FactionId,
+    /// `None` if the territory is unclaimed.
+    #[serde(default, with = "faction")]
+    pub defending_faction: Option<FactionId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_score: Option<u32>,
+    pub started: Timestamp,
+    pub ends: Timestamp,
+}
+
+impl TerritoryWar {
+    pub fn involves(&self, faction: FactionId) -> bool {
+        self.assaulting_faction == faction || self.defending_faction == Some(faction)
+    }
+}
+
+/// The `territory` selection.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerritoryResponse {
+    #[serde(deserialize_with = "territory_map::deserialize")]
+    pub territory: TerritoryMap<Territory>,
+}
+
+impl TerritoryResponse {
+    /// The owner of every claimed territory.
+    pub fn ownership(&self) -> TerritoryMap<FactionId> {
+        self.territory
+            .iter()
+            .filter_map(|(id, territory)| Some((id, territory.faction?)))
+            .collect()
+    }
+
+    pub fn territories_of(&self, faction: FactionId) -> TerritorySet {
+        self.territory
+            .iter()
+            .filter(|(_, territory)| territory.faction == Some(faction))
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+/// The `rackets` selection.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RacketsResponse {
+    #[serde(deserialize_with = "territory_map::deserialize")]
+    pub rackets: TerritoryMap<Racket>,
+}
+
+impl RacketsResponse {
+    /// The owner of every claimed territory with a racket.
+    pub fn ownership(&self) -> TerritoryMap<FactionId> {
+        self.rackets
+            .iter()
+            .filter_map(|(id, racket)| Some((id, racket.faction?)))
+            .collect()
+    }
+}
+
+/// The `territorywars` selection.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerritoryWarsResponse {
+    #[serde(
+        rename = "territorywars",
+        deserialize_with = "territory_map::deserialize"
+    )]
+    pub territory_wars: TerritoryMap<TerritoryWar>,
+}
+
+impl TerritoryWarsResponse {
+    /// All territories which are currently being assaulted.
+    pub fn contested(&self) -> TerritorySet {
+        self.territory_wars.keys()
+    }
+
+    /// The territories of wars in which the faction is assaulting or defending.
+    pub fn involving(&self, faction: FactionId) -> TerritorySet {
+        self.territory_wars
+            .iter()
+            .filter(|(_, war)| war.involves(faction))
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+/// Faction ids where `0` (or `null`) means no faction.
+mod faction {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::FactionId;
+
+    pub fn serialize<S>(faction: &Option<FactionId>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u32(faction.unwrap_or(0))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<FactionId>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let faction = Option::<FactionId>::deserialize(deserializer)?;
+        Ok(faction.filter(|id| *id != 0))
+    }
+}
+
+/// The API returns an empty array instead of an empty object if there are no entries.
+mod territory_map {
+    use core::marker::PhantomData;
+
+    use serde::{de, Deserialize, Deserializer};
+
+    use crate::TerritoryMap;
+
+    pub fn deserialize<'de, D, V>(deserializer: D) -> Result<TerritoryMap<V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Deserialize<'de>,
+    {
+        struct MapVisitor<V>(PhantomData<V>);
+        impl<'de, V: Deserialize<'de>> de::Visitor<'de> for MapVisitor<V> {
+            type Value = TerritoryMap<V>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                write!(formatter, "a map keyed by territory ids or an empty array")
+            }
+
+            fn visit_map<A>(self, access: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                TerritoryMap::deserialize(de::value::MapAccessDeserializer::new(access))
+            }
+
+            fn visit_seq<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                match access.next_element::<de::IgnoredAny>()? {
+                    None => Ok(TerritoryMap::new()),
+                    Some(_) => Err(de::Error::invalid_length(1, &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(MapVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{territory, TerritoryDataset};
+
+    const TERRITORY: &str = include_str!("../tests/fixtures/torn_api/territory.json");
+    const RACKETS: &str = include_str!("../tests/fixtures/torn_api/rackets.json");
+    const WARS: &str = include_str!("../tests/fixtures/torn_api/territorywars.json");
+    const WARS_EMPTY: &str = include_str!("../tests/fixtures/torn_api/territorywars_empty.json");
+
+    #[test]
+    fn test_territory() {
+        let response: TerritoryResponse = serde_json::from_str(TERRITORY).unwrap();
+        assert_eq!(response.territory.len(), 5);
+
+        let xod = &response.territory[territory!("XOD")];
+        assert_eq!(xod.sector, territory!("XOD").info().sector);
+        assert_eq!(xod.faction, Some(8151));
+        assert_eq!(xod.racket.as_ref().unwrap().level, 3);
+        assert_eq!(response.territory[territory!("VOD")].faction, None);
+
+        let ownership = response.ownership();
+        assert_eq!(ownership.len(), 4);
+        assert_eq!(ownership[territory!("GVE")], 9356);
+        assert!(!ownership.contains_key(territory!("VOD")));
+
+        let faction = response.territories_of(8151);
+        assert_eq!(faction.len(), 2);
+        // the unclaimed VOD borders XOD
+        assert!(faction
+            .neighbors(TerritoryDataset::embedded())
+            .contains(territory!("VOD")));
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TerritoryResponse>(&json).unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn test_rackets() {
+        let response: RacketsResponse = serde_json::from_str(RACKETS).unwrap();
+        assert_eq!(response.rackets[territory!("ZZB")].name, "Pawn Shop I");
+
+        let ownership = response.ownership();
+        assert_eq!(ownership.len(), 1);
+        assert_eq!(ownership[territory!("XOD")], 8151);
+    }
+
+    #[test]
+    fn test_territory_wars() {
+        let response: TerritoryWarsResponse = serde_json::from_str(WARS).unwrap();
+        let gve = &response.territory_wars[territory!("GVE")];
+        assert_eq!(gve.defending_faction, Some(9356));
+        assert_eq!(gve.required_score, Some(3200));
+        assert!(gve.ends > gve.started);
+
+        let vod = &response.territory_wars[territory!("VOD")];
+        assert_eq!(vod.defending_faction, None);
+        assert_eq!(vod.score, None);
+
+        assert_eq!(response.contested().len(), 2);
+        assert_eq!(
+            response.involving(8151).iter().collect::<Vec<_>>(),
+            [territory!("GVE")]
+        );
+        assert_eq!(response.involving(9356).len(), 2);
+
+        let empty: TerritoryWarsResponse = serde_json::from_str(WARS_EMPTY).unwrap();
+        assert!(empty.territory_wars.is_empty());
+        assert!(
+            serde_json::from_str::<TerritoryWarsResponse>(r#"{ "territorywars": [1] }"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_combined_selections() {
+        let mut combined: serde_json::Value = serde_json::from_str(TERRITORY).unwrap();
+        let wars: serde_json::Value = serde_json::from_str(WARS).unwrap();
+        combined["territorywars"] = wars["territorywars"].clone();
+        let combined = combined.to_string();
+
+        let territory: TerritoryResponse = serde_json::from_str(&combined).unwrap();
+        let wars: TerritoryWarsResponse = serde_json::from_str(&combined).unwrap();
+        assert!(wars.contested().is_subset(&territory.territory.keys()));
+    }
+}
@@ -0,0 +1,363 @@
+//! Parsing and validation of datasets in the `territory_shapes.json` schema.
+//!
+//! This module is shared between the build script, which validates the embedded dataset, and
+//! [`crate::TerritoryDataset`], which validates datasets loaded at runtime.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use super::{MAP_HEIGHT, MAP_WIDTH};
+
+/// Some shapes along the map edges overshoot it by rounding errors, which is harmless.
+const MAP_TOLERANCE: f64 = 0.5f64;
+
+/// Strings are borrowed from the input unless they contain escape sequences.
+#[derive(serde::Deserialize)]
+pub struct RawTerritory<'a> {
+    #[serde(borrow)]
+    pub shape: Cow<'a, str>,
+    pub db_id: i32,
+    pub sector: u8,
+    pub slots: u16,
+    #[serde(borrow)]
+    pub neighbors: Option<Vec<Cow<'a, str>>>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawKey<'a>(#[serde(borrow)] Cow<'a, str>);
+
+/// All entries of the dataset in file order. Unlike a `HashMap` this keeps duplicate keys around
+/// so that they can be reported.
+pub struct RawEntries<'a>(pub Vec<(Cow<'a, str>, RawTerritory<'a>)>);
+
+impl<'de: 'a, 'a> serde::Deserialize<'de> for RawEntries<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct EntriesVisitor<'a>(std::marker::PhantomData<&'a ()>);
+        impl<'de: 'a, 'a> serde::de::Visitor<'de> for EntriesVisitor<'a> {
+            type Value = RawEntries<'a>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "a map of territory ids to territories")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+                while let Some((RawKey(id), territory)) = map.next_entry()? {
+                    entries.push((id, territory));
+                }
+                Ok(RawEntries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor(std::marker::PhantomData))
+    }
+}
+
+pub fn is_valid_id(id: &str) -> bool {
+    id.len() == 3 && id.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+pub fn parse_shape(shape: &str) -> Result<Vec<svgtypes::SimplePathSegment>, String> {
+    svgtypes::SimplifyingPathParser::from(shape)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|why| format!("shape could not be parsed: {why}"))
+}
+
+fn validate_shape(segments: &[svgtypes::SimplePathSegment]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if segments.is_empty() {
+        problems.push("shape is empty".to_owned());
+        return problems;
+    }
+
+    let width = MAP_WIDTH as f64;
+    let height = MAP_HEIGHT as f64;
+
+    let mut open = false;
+    let mut out_of_bounds = Vec::new();
+    for segment in segments {
+        let end = match *segment {
+            svgtypes::SimplePathSegment::MoveTo { x, y } => {
+                if open {
+                    problems.push("shape contains a sub-path which is not closed".to_owned());
+                }
+                open = true;
+                Some((x, y))
+            }
+            svgtypes::SimplePathSegment::LineTo { x, y }
+            | svgtypes::SimplePathSegment::Quadratic { x, y, .. }
+            | svgtypes::SimplePathSegment::CurveTo { x, y, .. } => Some((x, y)),
+            svgtypes::SimplePathSegment::ClosePath => {
+                open = false;
+                None
+            }
+        };
+
+        if let Some((x, y)) = end {
+            if !(-MAP_TOLERANCE..=width + MAP_TOLERANCE).contains(&x)
+                || !(-MAP_TOLERANCE..=height + MAP_TOLERANCE).contains(&y)
+            {
+                out_of_bounds.push(format!("({x}, {y})"));
+            }
+        }
+    }
+
+    if open {
+        problems.push("shape is not closed".to_owned());
+    }
+
+    if !out_of_bounds.is_empty() {
+        problems.push(format!(
+            "shape has points outside of the {MAP_WIDTH}x{MAP_HEIGHT} map: {}",
+            out_of_bounds.join(", ")
+        ));
+    }
+
+    problems
+}
+
+/// Checks the whole dataset and collects every problem together with the offending territory key,
+/// so that a broken data update can be fixed in one go. On success the parsed shapes are returned.
+pub fn validate(
+    entries: &[(Cow<str>, RawTerritory)],
+) -> Result<HashMap<String, Vec<svgtypes::SimplePathSegment>>, Vec<String>> {
+    let mut problems = Vec::new();
+    let mut shapes = HashMap::with_capacity(entries.len());
+
+    let mut seen_ids = HashSet::new();
+    let mut db_ids: HashMap<i32, &str> = HashMap::new();
+    // duplicate ids are reported below, their neighbors are merged so that the back references
+    // of either entry are found
+    let mut neighbors: HashMap<&str, HashSet<&str>> = HashMap::with_capacity(entries.len());
+    for (id, tert) in entries {
+        neighbors
+            .entry(&**id)
+            .or_default()
+            .extend(tert.neighbors.iter().flatten().map(|n| &**n));
+    }
+
+    for (id, tert) in entries {
+        let id: &str = id;
+        if !seen_ids.insert(id) {
+            problems.push(format!("'{id}': duplicate territory id"));
+        }
+
+        if !is_valid_id(id) {
+            problems.push(format!(
+                "'{id}': territory ids must consist of exactly three uppercase ASCII letters"
+            ));
+        }
+
+        if let Some(other) = db_ids.insert(tert.db_id, id) {
+            problems.push(format!(
+                "'{id}': db_id {} is already used by '{other}'",
+                tert.db_id
+            ));
+        }
+
+        let mut seen_neighbors = HashSet::new();
+        for neighbor in tert.neighbors.iter().flatten() {
+            let neighbor: &str = neighbor;
+            if !seen_neighbors.insert(neighbor) {
+                problems.push(format!("'{id}': neighbor '{neighbor}' is listed twice"));
+            }
+
+            if neighbor == id {
+                problems.push(format!("'{id}': territory lists itself as a neighbor"));
+                continue;
+            }
+
+            match neighbors.get(neighbor) {
+                None => problems.push(format!(
+                    "'{id}': neighbor '{neighbor}' does not exist in the dataset"
+                )),
+                Some(back) if !back.contains(id) => problems.push(format!(
+                    "'{id}': neighbor '{neighbor}' does not list '{id}' as its neighbor"
+                )),
+                Some(_) => (),
+            }
+        }
+
+        match parse_shape(&tert.shape) {
+            Ok(segments) => {
+                problems.extend(
+                    validate_shape(&segments)
+                        .into_iter()
+                        .map(|problem| format!("'{id}': {problem}")),
+                );
+                shapes.insert(id.to_string(), segments);
+            }
+            Err(why) => problems.push(format!("'{id}': {why}")),
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(shapes)
+    } else {
+        Err(problems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &str = "M 10 10 L 20 10 L 20 20 Z";
+
+    fn problems(json: &str) -> Vec<String> {
+        let RawEntries(entries) = serde_json::from_str(json).unwrap();
+        validate(&entries).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_valid() {
+        let json = format!(
+            r#"{{
+                "AAA": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["BBB"] }},
+                "BBB": {{ "shape": "{SQUARE}", "db_id": 2, "sector": 1, "slots": 1, "neighbors": ["AAA"] }}
+            }}"#
+        );
+        assert_eq!(problems(&json), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_duplicate_db_id() {
+        let json = format!(
+            r#"{{
+                "AAA": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1 }},
+                "BBB": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1 }}
+            }}"#
+        );
+        assert_eq!(
+            problems(&json),
+            vec!["'BBB': db_id 1 is already used by 'AAA'"]
+        );
+    }
+
+    #[test]
+    fn test_self_neighbor() {
+        let json = format!(
+            r#"{{ "AAA": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["AAA"] }} }}"#
+        );
+        assert_eq!(
+            problems(&json),
+            vec!["'AAA': territory lists itself as a neighbor"]
+        );
+    }
+
+    #[test]
+    fn test_asymmetric_neighbor() {
+        let json = format!(
+            r#"{{
+                "AAA": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["BBB"] }},
+                "BBB": {{ "shape": "{SQUARE}", "db_id": 2, "sector": 1, "slots": 1 }}
+            }}"#
+        );
+        assert_eq!(
+            problems(&json),
+            vec!["'AAA': neighbor 'BBB' does not list 'AAA' as its neighbor"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_neighbor() {
+        let json = format!(
+            r#"{{ "AAA": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["BBB"] }} }}"#
+        );
+        assert_eq!(
+            problems(&json),
+            vec!["'AAA': neighbor 'BBB' does not exist in the dataset"]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_neighbor() {
+        let json = format!(
+            r#"{{
+                "AAA": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["BBB", "BBB"] }},
+                "BBB": {{ "shape": "{SQUARE}", "db_id": 2, "sector": 1, "slots": 1, "neighbors": ["AAA"] }}
+            }}"#
+        );
+        assert_eq!(
+            problems(&json),
+            vec!["'AAA': neighbor 'BBB' is listed twice"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_id() {
+        let json = format!(
+            r#"{{ "aaa": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1 }} }}"#
+        );
+        assert_eq!(
+            problems(&json),
+            vec!["'aaa': territory ids must consist of exactly three uppercase ASCII letters"]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_id_neighbors() {
+        // each entry's own neighbors are checked, even if a later entry reuses its id
+        let json = format!(
+            r#"{{
+                "AAA": {{ "shape": "{SQUARE}", "db_id": 1, "sector": 1, "slots": 1, "neighbors": ["AAA"] }},
+                "AAA": {{ "shape": "{SQUARE}", "db_id": 2, "sector": 1, "slots": 1 }}
+            }}"#
+        );
+        assert_eq!(
+            problems(&json),
+            vec![
+                "'AAA': territory lists itself as a neighbor",
+                "'AAA': duplicate territory id"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_shape() {
+        let json = r#"{ "AAA": { "shape": "M 10 10 L 20 10 L 20 20", "db_id": 1, "sector": 1, "slots": 1 } }"#;
+        assert_eq!(problems(json), vec!["'AAA': shape is not closed"]);
+    }
+
+    #[test]
+    fn test_unparseable_shape() {
+        let json =
+            r#"{ "AAA": { "shape": "M 10 10 X 20 10 Z", "db_id": 1, "sector": 1, "slots": 1 } }"#;
+        let problems = problems(json);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("'AAA': shape could not be parsed: "));
+    }
+
+    #[test]
+    fn test_empty_shape() {
+        let json = r#"{ "AAA": { "shape": "", "db_id": 1, "sector": 1, "slots": 1 } }"#;
+        assert_eq!(problems(json), vec!["'AAA': shape is empty"]);
+    }
+
+    #[test]
+    fn test_unclosed_sub_path() {
+        let json = r#"{ "AAA": { "shape": "M 10 10 L 20 10 L 20 20 M 30 30 L 40 30 L 40 40 Z", "db_id": 1, "sector": 1, "slots": 1 } }"#;
+        assert_eq!(
+            problems(json),
+            vec!["'AAA': shape contains a sub-path which is not closed"]
+        );
+    }
+
+    #[test]
+    fn test_out_of_map_shape() {
+        let json = r#"{ "AAA": { "shape": "M 10 10 L 7000 10 L 20 -20 Z", "db_id": 1, "sector": 1, "slots": 1 } }"#;
+        assert_eq!(
+            problems(json),
+            vec!["'AAA': shape has points outside of the 6256x3648 map: (7000, 10), (20, -20)"]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! JavaScript bindings for rendering territory views in the browser.
+//!
+//! The map tiles are not embedded into the WebAssembly module. Instead they have to be fetched
+//! from `static/map_tiles` and passed to `MapRenderer.addTile`, `MapRenderer.requiredTiles` lists
+//! the tiles which are needed for a view port. The module can be built with
+//!
+//! ```text
+//! cargo rustc -p torn-territories --lib --crate-type cdylib --release \
+//!     --target wasm32-unknown-unknown --no-default-features --features wasm
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/release/torn_territories.wasm
+//! ```
+//!
+//! Fill and stroke layers use the same JSON schema as [`RenderLayer`].
+
+use std::io::Cursor;
+
+use image::{buffer::ConvertBuffer, ImageFormat, RgbaImage};
+use wasm_bindgen::{prelude::*, Clamped};
+use web_sys::ImageData;
+
+use crate::{
+    load_map_segment_from, tile_names, tiles::X4_TILE, MemoryTiles, OutputFormat, RenderLayer,
+    RenderScale, RenderSpec, TerritoryDataset, TerritoryId, Viewport, MAP_HEIGHT, MAP_WIDTH,
+};
+
+/// Parses a territory ID and returns it in its canonical form, e.g. ` xod` becomes `XOD`.
+#[wasm_bindgen(js_name = parseTerritoryId)]
+pub fn parse_territory_id(id: &str) -> Result<String, JsError> {
+    let id = TerritoryId::parse_lenient(id)?;
+    Ok(id.to_string())
+}
+
+/// Renders territories with the embedded dataset and map tiles that were loaded at runtime.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct MapRenderer {
+    tiles: MemoryTiles,
+}
+
+#[wasm_bindgen]
+impl MapRenderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a TIFF tile, `name` is its file name, e.g. `map_1_1.tiff`.
+    #[wasm_bindgen(js_name = addTile)]
+    pub fn add_tile(&mut self, name: String, data: Vec<u8>) {
+        self.tiles.insert(name, data);
+    }
+
+    /// Names of the tiles which are needed for the view port but have not been added yet.
+    #[wasm_bindgen(js_name = requiredTiles)]
+    pub fn required_tiles(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        scale: Option<String>,
+    ) -> Result<Vec<String>, JsError> {
+        let names = match parse_scale(scale.as_deref())? {
+            Some(RenderScale::X4) => vec![X4_TILE.to_owned()],
+            _ => {
+                check_bounds(x, y, width, height)?;
+                tile_names(x, y, width, height)
+            }
+        };
+
+        Ok(names
+            .into_iter()
+            .filter(|name| !self.tiles.contains(name))
+            .collect())
+    }
+
+    /// Renders the view port and returns it as a PNG. `options` is an object with optional `fill`
+    /// and `stroke` arrays of layers and `scale`, which is either `"x1"` (the default) or `"x4"`.
+    #[wasm_bindgen(js_name = renderTerritories)]
+    pub fn render_territories(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        options: JsValue,
+    ) -> Result<Vec<u8>, JsError> {
+        let image = self.render_image(x, y, width, height, &options)?;
+        encode_png(image)
+    }
+
+    /// Like `renderTerritories`, but returns `ImageData` which can be drawn onto a canvas.
+    #[wasm_bindgen(js_name = renderTerritoriesImageData)]
+    pub fn render_territories_image_data(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        options: JsValue,
+    ) -> Result<ImageData, JsError> {
+        let image = self.render_image(x, y, width, height, &options)?;
+        image_data(&image)
+    }
+
+    /// Renders a [`RenderSpec`] and returns the image in the format requested by the spec.
+    #[wasm_bindgen(js_name = renderSpec)]
+    pub fn render_spec(&self, spec: JsValue) -> Result<Vec<u8>, JsError> {
+        let spec: RenderSpec = from_js(&spec)?;
+        Ok(TerritoryDataset::embedded().render_spec_with_tiles(&self.tiles, &spec)?)
+    }
+
+    /// The greyscale map background of the given area at full resolution as a PNG.
+    #[wasm_bindgen(js_name = loadMapSegment)]
+    pub fn load_map_segment(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, JsError> {
+        check_bounds(x, y, width, height)?;
+        let segment = load_map_segment_from(&self.tiles, x, y, width, height)?;
+        encode_png(segment.convert())
+    }
+
+    /// Like `loadMapSegment`, but returns `ImageData` which can be drawn onto a canvas.
+    #[wasm_bindgen(js_name = loadMapSegmentImageData)]
+    pub fn load_map_segment_image_data(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<ImageData, JsError> {
+        check_bounds(x, y, width, height)?;
+        let segment = load_map_segment_from(&self.tiles, x, y, width, height)?;
+        image_data(&segment.convert())
+    }
+}
+
+impl MapRenderer {
+    fn render_image(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        options: &JsValue,
+    ) -> Result<RgbaImage, JsError> {
+        let options: RenderOptions = if options.is_undefined() || options.is_null() {
+            RenderOptions::default()
+        } else {
+            from_js(options)?
+        };
+
+        let spec = RenderSpec {
+            viewport: Viewport::Rect {
+                x,
+                y,
+                width,
+                height,
+            },
+            scale: options.scale,
+            fill: options.fill,
+            stroke: options.stroke,
+            format: OutputFormat::Png,
+        };
+
+        Ok(TerritoryDataset::embedded().render_spec_image_with_tiles(&self.tiles, &spec)?)
+    }
+}
+
+fn check_bounds(x: u32, y: u32, width: u32, height: u32) -> Result<(), JsError> {
+    if width == 0
+        || height == 0
+        || x.saturating_add(width) > MAP_WIDTH
+        || y.saturating_add(height) > MAP_HEIGHT
+    {
+        Err(JsError::new("viewport is empty or outside of the map"))
+    } else {
+        Ok(())
+    }
+}
+
+fn parse_scale(scale: Option<&str>) -> Result<Option<RenderScale>, JsError> {
+    match scale {
+        None => Ok(None),
+        Some("x1") => Ok(Some(RenderScale::X1)),
+        Some("x4") => Ok(Some(RenderScale::X4)),
+        Some(other) => Err(JsError::new(&format!(
+            "unknown scale '{other}', expected 'x1' or 'x4'"
+        ))),
+    }
+}
+
+#[derive(Default, serde::Deserialize)]
+struct RenderOptions {
+    #[serde(default)]
+    fill: Vec<RenderLayer>,
+    #[serde(default)]
+    stroke: Vec<RenderLayer>,
+    scale: Option<RenderScale>,
+}
+
+/// Converts a plain JS value through JSON, which keeps the schema identical to the Rust API.
+fn from_js<T: serde::de::DeserializeOwned>(value: &JsValue) -> Result<T, JsError> {
+    let json = js_sys::JSON::stringify(value)
+        .ok()
+        .and_then(|json| json.as_string())
+        .ok_or_else(|| JsError::new("value cannot be converted to JSON"))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn encode_png(image: RgbaImage) -> Result<Vec<u8>, JsError> {
+    let mut buf = Vec::new();
+    image::DynamicImage::ImageRgba8(image)
+        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)?;
+    Ok(buf)
+}
+
+fn image_data(image: &RgbaImage) -> Result<ImageData, JsError> {
+    ImageData::new_with_u8_clamped_array_and_sh(
+        Clamped(image.as_raw()),
+        image.width(),
+        image.height(),
+    )
+    .map_err(|_| JsError::new("ImageData is not available in this environment"))
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    fn renderer() -> MapRenderer {
+        let mut renderer = MapRenderer::new();
+        renderer.add_tile(
+            "map_1_1.tiff".to_owned(),
+            include_bytes!("../static/map_tiles/map_1_1.tiff").to_vec(),
+        );
+        renderer
+    }
+
+    fn options() -> JsValue {
+        js_sys::JSON::parse(
+            r##"{ "fill": [{ "colour": "#ff0000", "opacity": 0.5, "territories": ["XOD"] }] }"##,
+        )
+        .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_parse_territory_id() {
+        assert_eq!(parse_territory_id(" xod").ok().unwrap(), "XOD");
+        assert!(parse_territory_id("ZZZ").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_required_tiles() {
+        let renderer = renderer();
+        assert!(renderer
+            .required_tiles(100, 100, 64, 48, None)
+            .ok()
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            renderer
+                .required_tiles(590, 100, 20, 20, Some("x1".to_owned()))
+                .ok()
+                .unwrap(),
+            vec!["map_2_1.tiff"]
+        );
+        assert_eq!(
+            renderer
+                .required_tiles(0, 0, MAP_WIDTH, MAP_HEIGHT, Some("x4".to_owned()))
+                .ok()
+                .unwrap(),
+            vec![X4_TILE]
+        );
+        assert!(renderer
+            .required_tiles(0, 0, 10, 10, Some("x2".to_owned()))
+            .is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_render_territories() {
+        let renderer = renderer();
+        let png = renderer
+            .render_territories(100, 100, 64, 48, options())
+            .ok()
+            .unwrap();
+        let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 48));
+
+        // the tile to the right has not been added
+        assert!(renderer
+            .render_territories(590, 100, 20, 20, JsValue::UNDEFINED)
+            .is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_load_map_segment() {
+        let renderer = renderer();
+        let png = renderer.load_map_segment(10, 10, 30, 20).ok().unwrap();
+        let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!((image.width(), image.height()), (30, 20));
+
+        assert!(renderer.load_map_segment(MAP_WIDTH, 0, 1, 1).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_render_spec() {
+        let spec = js_sys::JSON::parse(
+            r##"{
+                "viewport": { "type": "rect", "x": 100, "y": 100, "width": 64, "height": 48 },
+                "fill": [{ "colour": "#00ff00", "opacity": 1.0, "territories": ["XOD"] }],
+                "format": "tiff"
+            }"##,
+        )
+        .unwrap();
+        let tiff = renderer().render_spec(spec).ok().unwrap();
+        let image = image::load_from_memory_with_format(&tiff, ImageFormat::Tiff).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 48));
+    }
+}